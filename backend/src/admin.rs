@@ -0,0 +1,115 @@
+use candid::Principal;
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+/// Call once from `#[ic_cdk::init]` to seed the controller registry with the
+/// deploying principal, so the canister never starts out with no one able
+/// to call `add_controller`.
+pub fn init_controller(controller: Principal) {
+    STATE.with(|s| {
+        s.borrow_mut().controllers.insert(controller);
+    });
+}
+
+pub fn is_controller(principal: Principal) -> bool {
+    STATE.with(|s| s.borrow().controllers.contains(&principal))
+}
+
+/// Guards a controller-only endpoint; call at the top of the handler.
+pub fn require_controller() -> Result<(), SwapError> {
+    let caller = ic_cdk::caller();
+    if is_controller(caller) {
+        Ok(())
+    } else {
+        Err(SwapError::Unauthorized)
+    }
+}
+
+/// Alias kept for the many endpoints across this canister that already call
+/// it under this name; backed by the same controller registry as
+/// `require_controller`.
+pub fn require_admin() -> Result<(), SwapError> {
+    require_controller()
+}
+
+/// Adds `p` as an additional controller. Only an existing controller may
+/// call this, so control can never be granted by someone who doesn't
+/// already have it.
+#[ic_cdk::update]
+pub fn add_controller(p: Principal) -> Result<(), SwapError> {
+    require_controller()?;
+    STATE.with(|s| s.borrow_mut().controllers.insert(p));
+    Ok(())
+}
+
+/// Removes `p` as a controller. Only an existing controller may call this.
+/// Refuses to remove the last remaining controller, since that would leave
+/// the canister with no one able to call this or `add_controller` again.
+#[ic_cdk::update]
+pub fn remove_controller(p: Principal) -> Result<(), SwapError> {
+    require_controller()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.controllers.len() <= 1 && state.controllers.contains(&p) {
+            return Err(SwapError::InvalidAmount(
+                "cannot remove the last remaining controller".into(),
+            ));
+        }
+        state.controllers.remove(&p);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `ic_cdk::caller()` always resolves to the anonymous principal in this
+    // non-canister test harness, so that's the principal these tests grant
+    // (or withhold) controller status from.
+    fn caller() -> Principal {
+        Principal::anonymous()
+    }
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn controller_check_matches_initialized_principal() {
+        reset_state();
+        let controller = caller();
+        init_controller(controller);
+        assert!(is_controller(controller));
+    }
+
+    #[test]
+    fn a_non_controller_caller_is_rejected() {
+        reset_state();
+        assert_eq!(require_controller(), Err(SwapError::Unauthorized));
+        assert!(add_controller(caller()).is_err());
+    }
+
+    #[test]
+    fn a_controller_caller_can_add_and_then_remove_another_principal() {
+        reset_state();
+        init_controller(caller());
+        assert!(require_controller().is_ok());
+
+        let other = Principal::from_slice(&[1u8; 29]);
+        add_controller(other).unwrap();
+        assert!(is_controller(other));
+
+        remove_controller(other).unwrap();
+        assert!(!is_controller(other));
+    }
+
+    #[test]
+    fn removing_the_last_controller_is_rejected() {
+        reset_state();
+        init_controller(caller());
+        assert!(remove_controller(caller()).is_err());
+        assert!(is_controller(caller()));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::errors::SwapError;
+use crate::state::STATE;
+use crate::types::{Chain, SwapOrderStatus};
+
+/// Tracks whether a chain's RPC/settlement path is currently usable. Updated
+/// by health checks or admins; consulted before routing new work to a chain.
+pub fn is_chain_healthy(chain: Chain) -> bool {
+    STATE.with(|s| {
+        s.borrow()
+            .unhealthy_chains
+            .get(&chain)
+            .map(|unhealthy| !unhealthy)
+            .unwrap_or(true)
+    })
+}
+
+pub(crate) fn set_chain_health_internal(chain: Chain, healthy: bool) {
+    STATE.with(|s| {
+        s.borrow_mut().unhealthy_chains.insert(chain, !healthy);
+    });
+}
+
+/// Controller-only: marks `chain` healthy or unhealthy, e.g. after an
+/// operator-run health check. Previously callable by anyone, which let a
+/// caller mark a healthy chain unhealthy to redirect order routing.
+#[ic_cdk::update]
+pub fn set_chain_health(chain: Chain, healthy: bool) -> Result<(), SwapError> {
+    crate::admin::require_controller()?;
+    set_chain_health_internal(chain, healthy);
+    Ok(())
+}
+
+/// Moves an unpaired order's destination chain to a healthy alternative when
+/// its original destination chain has gone unhealthy. Paired/escrowed orders
+/// are left alone since their HTLC is already anchored to the old chain.
+#[ic_cdk::update]
+pub fn migrate_order_destination_chain(
+    order_id: String,
+    new_dst_chain: Chain,
+) -> Result<(), SwapError> {
+    if !is_chain_healthy(new_dst_chain) {
+        return Err(SwapError::InvalidAmount(
+            "destination chain for migration is itself unhealthy".into(),
+        ));
+    }
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let order = state
+            .orders
+            .get_mut(&order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.clone()))?;
+
+        if order.status != SwapOrderStatus::Created {
+            return Err(SwapError::InvalidAmount(
+                "order can only be migrated before pairing".into(),
+            ));
+        }
+        if is_chain_healthy(order.dst_chain) {
+            return Err(SwapError::InvalidAmount(
+                "order's destination chain is healthy; migration not needed".into(),
+            ));
+        }
+
+        order.dst_chain = new_dst_chain;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CreateOrderRequest;
+    use candid::Principal;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    fn create_order() -> String {
+        crate::orders::create_cross_chain_swap_order_internal(
+            Principal::anonymous(),
+            CreateOrderRequest {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn migration_moves_destination_when_unhealthy() {
+        reset_state();
+        let order_id = create_order();
+        set_chain_health_internal(Chain::Ethereum, false);
+
+        migrate_order_destination_chain(order_id.clone(), Chain::Base).unwrap();
+
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].dst_chain, Chain::Base);
+        });
+    }
+
+    #[test]
+    fn migration_rejected_when_destination_already_healthy() {
+        reset_state();
+        let order_id = create_order();
+        assert!(migrate_order_destination_chain(order_id, Chain::Base).is_err());
+    }
+
+    #[test]
+    fn a_non_controller_cannot_set_chain_health() {
+        reset_state();
+        assert!(set_chain_health(Chain::Ethereum, false).is_err());
+    }
+
+    #[test]
+    fn a_controller_can_set_chain_health() {
+        reset_state();
+        crate::admin::init_controller(candid::Principal::anonymous());
+
+        set_chain_health(Chain::Ethereum, false).unwrap();
+        assert!(!is_chain_healthy(Chain::Ethereum));
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Chain, Timelocks};
+
+/// Canister-wide tunables that admins can adjust post-deploy.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CanisterConfig {
+    /// Minimum safety deposit required per order, in basis points of order value.
+    pub safety_deposit_bps: u32,
+    /// Maximum allowed size, in bytes, of an HTTP outcall response body. Bounds
+    /// the cycles an RPC call can burn and the memory a malicious/misbehaving
+    /// endpoint can force the canister to hold.
+    pub max_rpc_response_bytes: u64,
+    /// Minimum time a maker must wait before re-creating an order with the
+    /// same parameters after cancelling one, to deter cancel/re-create spam.
+    pub cancel_recreate_cooldown_secs: u64,
+    /// Whether to verify the destination chain holds enough liquidity before
+    /// an order can be paired. Disabling this is only for emergencies/testing.
+    pub require_preflight_balance_check: bool,
+    /// Caps concurrent HTTP outcalls so the canister can't exceed the IC's
+    /// per-canister in-flight call limit.
+    pub max_in_flight_outcalls: u32,
+    /// How long a cached SPL mint -> token-program lookup stays valid before
+    /// it must be re-queried from the chain.
+    pub token_program_cache_ttl_secs: u64,
+    /// Default lifetime of an order before it becomes eligible for refund.
+    pub default_order_ttl_secs: u64,
+    /// Tolerance subtracted from the current time before comparing against a
+    /// permit's `deadline`, so a client with a slightly-behind clock isn't
+    /// rejected right at the boundary.
+    pub permit_clock_skew_tolerance_secs: u64,
+    /// Maximum amount of destination-chain gas the canister will sponsor for
+    /// a single principal per day, across all of that principal's orders.
+    pub daily_gas_sponsorship_limit: u128,
+    /// How long a Solana replay-guard entry (the idempotency cache) is kept
+    /// before it's pruned as stale.
+    pub idempotency_cache_ttl_secs: u64,
+    /// How long a cancel/re-create cooldown entry (the reservation cache) is
+    /// kept before it's pruned as stale.
+    pub reservation_cache_ttl_secs: u64,
+    /// Whether new orders can be paired with a resolver. Orders can still be
+    /// created while this is off; they just accumulate unpaired until it's
+    /// re-enabled, e.g. to halt matching during an incident without losing inflow.
+    pub pairing_enabled: bool,
+    /// How long a paired order has to reach a terminal state before the
+    /// heartbeat auto-refunds it rather than leaving escrow tied up
+    /// indefinitely by a stalled chain RPC.
+    pub coordination_timeout_secs: u64,
+    /// SPL token programs accepted for escrow. A mint owned by a program not
+    /// on this list (an exotic fork with non-standard transfer semantics) is
+    /// rejected before any funds move.
+    pub allowed_spl_token_programs: Vec<crate::solana::TokenProgram>,
+    /// Maximum number of orders allowed to sit unpaired (`Created`) at once.
+    /// New order creation is rejected with `BacklogFull` once this is
+    /// reached, applying backpressure before any funds are escrowed rather
+    /// than letting the backlog grow unbounded while pairing is slow or paused.
+    pub max_pending_pairing_backlog: usize,
+    /// Flat fee, in the escrowed token's smallest unit, reserved per chain
+    /// to cover the ledger/gas cost of actually sending a refund back to the
+    /// maker. Deducted from the refunded amount (the maker bears it, the
+    /// same party who bears the safety deposit for an unwound order), so an
+    /// escrow that exactly equals the order's nominal amount never causes
+    /// the refund itself to fail for lack of headroom — it simply nets
+    /// slightly less than that nominal amount.
+    pub refund_fee_by_chain: HashMap<Chain, u128>,
+    /// Flat cost, in the escrowed token's smallest unit, the canister
+    /// estimates it spends creating a destination-chain HTLC for an order —
+    /// gas on EVM chains, effectively free on ICP. Recorded onto the order as
+    /// `sunk_setup_cost` once the HTLC exists, so cancelling afterward
+    /// refunds the escrow minus this already-irrecoverable cost rather than
+    /// promising the maker the full amount back.
+    pub htlc_setup_cost_by_chain: HashMap<Chain, u128>,
+    /// How long past `expires_at` a refund stays blocked, to give a
+    /// counterparty's in-flight claim on the other chain time to settle
+    /// before the maker's funds are released back. Without this, a refund
+    /// submitted the instant the timelock passes could race a claim that
+    /// was already broadcast, letting both the claim and the refund land.
+    pub refund_grace_secs: u64,
+    /// How to round a destination amount when converting between decimals
+    /// loses precision. Defaults to `Floor`, keeping the lost remainder as
+    /// protocol dust rather than ever overpaying a recipient. See
+    /// `decimals::convert_and_record_dust`.
+    pub rounding_policy: crate::decimals::RoundingPolicy,
+    /// How often the timer-driven expiry sweep runs, in seconds. Separate
+    /// from the heartbeat, which already sweeps every round at a cadence
+    /// this canister doesn't control directly. See
+    /// `maintenance::schedule_expiry_sweep`.
+    pub expiry_sweep_interval_secs: u64,
+    /// Default `Timelocks` applied to an order whose `CreateOrderRequest`
+    /// doesn't specify its own. See `types::Timelocks`.
+    pub default_timelocks: Timelocks,
+    /// When set, new orders are rejected with `SwapError::Draining` instead
+    /// of being created, while everything already in flight (completion,
+    /// refund, settlement retry) keeps working. Meant for planned upgrades:
+    /// an operator flips this on, waits for `orders::get_inflight_count` to
+    /// reach zero, then upgrades without interrupting a mid-swap order.
+    pub draining: bool,
+}
+
+impl Default for CanisterConfig {
+    fn default() -> Self {
+        Self {
+            // 1% of order value by default.
+            safety_deposit_bps: 100,
+            max_rpc_response_bytes: 64 * 1024,
+            cancel_recreate_cooldown_secs: 60,
+            require_preflight_balance_check: true,
+            max_in_flight_outcalls: 30,
+            token_program_cache_ttl_secs: 24 * 60 * 60,
+            default_order_ttl_secs: 3 * 60 * 60,
+            permit_clock_skew_tolerance_secs: 120,
+            daily_gas_sponsorship_limit: 5_000_000_000_000_000_000, // 5 gas-equivalent units/day
+            idempotency_cache_ttl_secs: 24 * 60 * 60,
+            reservation_cache_ttl_secs: 24 * 60 * 60,
+            pairing_enabled: true,
+            coordination_timeout_secs: 30 * 60,
+            allowed_spl_token_programs: vec![
+                crate::solana::TokenProgram::SplToken,
+                crate::solana::TokenProgram::SplToken2022,
+            ],
+            max_pending_pairing_backlog: 10_000,
+            refund_fee_by_chain: HashMap::from([
+                (Chain::ICP, 10_000), // ICRC ledger transfer fee, e8s
+                (Chain::Ethereum, 1_000_000_000_000_000), // ~0.001 ETH at typical gas prices
+                (Chain::Base, 100_000_000_000_000), // L2s are cheaper
+                (Chain::Arbitrum, 100_000_000_000_000),
+                (Chain::Solana, 5_000), // lamports
+            ]),
+            htlc_setup_cost_by_chain: HashMap::from([
+                (Chain::ICP, 0),
+                (Chain::Ethereum, 2_000_000_000_000_000), // ~0.002 ETH at typical gas prices
+                (Chain::Base, 200_000_000_000_000),
+                (Chain::Arbitrum, 200_000_000_000_000),
+                (Chain::Solana, 0),
+            ]),
+            refund_grace_secs: 10 * 60,
+            rounding_policy: crate::decimals::RoundingPolicy::Floor,
+            expiry_sweep_interval_secs: 60,
+            default_timelocks: Timelocks {
+                src_withdrawal: 0,
+                src_cancellation: 60 * 60,
+                dst_withdrawal: 0,
+                dst_cancellation: 30 * 60,
+            },
+            draining: false,
+        }
+    }
+}
+
+/// Computes the minimum safety deposit owed for an order of `order_value`,
+/// given a `bps` (basis points, 1/100th of a percent) rate.
+pub fn required_safety_deposit(order_value: u128, bps: u32) -> u128 {
+    order_value * bps as u128 / 10_000
+}
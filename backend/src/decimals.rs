@@ -0,0 +1,300 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+/// Decimals counts above this can produce a `10^decimals` scale factor too
+/// large to be useful (and, for a difference near `u32::BITS`, one that
+/// `checked_pow` would reject outright), so tokens advertising more than
+/// this are rejected before any amount math is attempted.
+pub const MAX_SAFE_DECIMALS: u8 = 30;
+
+fn validate_decimals(decimals: u8) -> Result<(), SwapError> {
+    if decimals > MAX_SAFE_DECIMALS {
+        return Err(SwapError::InvalidAmount(format!(
+            "decimals {decimals} exceeds the maximum supported value of {MAX_SAFE_DECIMALS}"
+        )));
+    }
+    Ok(())
+}
+
+/// Rescales an amount expressed with `from_decimals` to one expressed with
+/// `to_decimals`, e.g. converting an SPL `u64` amount (commonly 6-9 decimals)
+/// into an ICRC/EVM `u128` amount (commonly 8-18 decimals). Used everywhere a
+/// swap crosses a token with a different decimals count on each leg.
+///
+/// Handles 0-decimal tokens (no rescaling needed beyond the identity/scale
+/// cases below) and rejects anything above `MAX_SAFE_DECIMALS` up front, so
+/// the scale computation below never has to guard against an unreasonable
+/// exponent on its own.
+pub fn convert_amount(amount: u128, from_decimals: u8, to_decimals: u8) -> Result<u128, SwapError> {
+    validate_decimals(from_decimals)?;
+    validate_decimals(to_decimals)?;
+    if from_decimals == to_decimals {
+        return Ok(amount);
+    }
+    if to_decimals > from_decimals {
+        let scale = 10u128
+            .checked_pow((to_decimals - from_decimals) as u32)
+            .ok_or_else(|| SwapError::InvalidAmount("decimals scale overflow".into()))?;
+        amount
+            .checked_mul(scale)
+            .ok_or_else(|| SwapError::InvalidAmount("amount overflowed while upscaling".into()))
+    } else {
+        let scale = 10u128
+            .checked_pow((from_decimals - to_decimals) as u32)
+            .ok_or_else(|| SwapError::InvalidAmount("decimals scale overflow".into()))?;
+        Ok(amount / scale)
+    }
+}
+
+/// Converts an SPL `u64` token amount into the canister's canonical `u128`
+/// representation at the given destination decimals.
+pub fn spl_u64_to_u128(amount: u64, spl_decimals: u8, dst_decimals: u8) -> Result<u128, SwapError> {
+    convert_amount(amount as u128, spl_decimals, dst_decimals)
+}
+
+/// Converts a canonical `u128` amount down into an SPL `u64` amount,
+/// rejecting values that would overflow `u64`.
+pub fn u128_to_spl_u64(amount: u128, src_decimals: u8, spl_decimals: u8) -> Result<u64, SwapError> {
+    let converted = convert_amount(amount, src_decimals, spl_decimals)?;
+    u64::try_from(converted).map_err(|_| SwapError::InvalidAmount("amount exceeds SPL u64 range".into()))
+}
+
+/// How to round a destination amount when a decimals conversion can't be
+/// represented exactly. `Floor` (the default) never pays out more than was
+/// actually received; `Ceil` never shortchanges the recipient, at the cost
+/// of the protocol covering the difference; `Nearest` picks whichever side
+/// of the remainder is closer, minimizing drift either direction.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RoundingPolicy {
+    #[default]
+    Floor,
+    Ceil,
+    Nearest,
+}
+
+/// Like `convert_amount`, but when downscaling loses precision, applies
+/// `policy` to pick the paid-out amount and reports the leftover `dust`
+/// (in `from_decimals` units) that amount didn't account for. Upscaling
+/// never loses precision, so dust is always zero there regardless of policy.
+pub fn convert_amount_with_rounding(
+    amount: u128,
+    from_decimals: u8,
+    to_decimals: u8,
+    policy: RoundingPolicy,
+) -> Result<(u128, u128), SwapError> {
+    validate_decimals(from_decimals)?;
+    validate_decimals(to_decimals)?;
+    if to_decimals >= from_decimals {
+        return Ok((convert_amount(amount, from_decimals, to_decimals)?, 0));
+    }
+
+    let scale = 10u128
+        .checked_pow((from_decimals - to_decimals) as u32)
+        .ok_or_else(|| SwapError::InvalidAmount("decimals scale overflow".into()))?;
+    let floor_amount = amount / scale;
+    let remainder = amount % scale;
+
+    Ok(match policy {
+        RoundingPolicy::Floor => (floor_amount, remainder),
+        RoundingPolicy::Ceil => {
+            if remainder == 0 { (floor_amount, 0) } else { (floor_amount + 1, scale - remainder) }
+        }
+        RoundingPolicy::Nearest => {
+            if remainder * 2 >= scale {
+                (floor_amount + 1, scale - remainder)
+            } else {
+                (floor_amount, remainder)
+            }
+        }
+    })
+}
+
+/// Admin-only: changes the rounding policy applied to destination-amount
+/// conversions across settlement.
+#[ic_cdk::update]
+pub fn set_rounding_policy(policy: RoundingPolicy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    set_rounding_policy_internal(policy);
+    Ok(())
+}
+
+fn set_rounding_policy_internal(policy: RoundingPolicy) {
+    STATE.with(|s| s.borrow_mut().config.rounding_policy = policy);
+}
+
+fn accrue_dust(token: &str, dust: u128) {
+    if dust == 0 {
+        return;
+    }
+    STATE.with(|s| {
+        *s.borrow_mut().accrued_dust.entry(token.to_string()).or_insert(0) += dust;
+    });
+}
+
+/// Converts `amount` using the canister's configured `RoundingPolicy`,
+/// recording any leftover dust against `token` so it doesn't just
+/// evaporate across repeated conversions. This is what a settlement leg
+/// crossing a decimals boundary should call instead of `convert_amount`
+/// directly.
+pub fn convert_and_record_dust(token: &str, amount: u128, from_decimals: u8, to_decimals: u8) -> Result<u128, SwapError> {
+    let policy = STATE.with(|s| s.borrow().config.rounding_policy);
+    let (converted, dust) = convert_amount_with_rounding(amount, from_decimals, to_decimals, policy)?;
+    accrue_dust(token, dust);
+    Ok(converted)
+}
+
+/// Total dust accrued for `token` so far, for reconciliation/sweeping.
+#[ic_cdk::query]
+pub fn get_accrued_dust(token: String) -> u128 {
+    STATE.with(|s| s.borrow().accrued_dust.get(&token).copied().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_decimals_is_identity() {
+        assert_eq!(convert_amount(1_000, 6, 6).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn upscales_from_spl_six_to_evm_eighteen() {
+        // 1.5 tokens at 6 decimals -> 1.5 tokens at 18 decimals.
+        assert_eq!(convert_amount(1_500_000, 6, 18).unwrap(), 1_500_000_000_000_000_000);
+    }
+
+    #[test]
+    fn downscales_losing_precision_by_truncation() {
+        // 1_234_567 at 6 decimals down to 2 decimals truncates, doesn't round.
+        assert_eq!(convert_amount(1_234_567, 6, 2).unwrap(), 123);
+    }
+
+    #[test]
+    fn round_trip_through_spl_u64() {
+        let spl_amount: u64 = 2_000_000; // 2.0 tokens at 6 decimals
+        let canonical = spl_u64_to_u128(spl_amount, 6, 18).unwrap();
+        let back = u128_to_spl_u64(canonical, 18, 6).unwrap();
+        assert_eq!(back, spl_amount);
+    }
+
+    #[test]
+    fn zero_decimal_token_upscales_to_eighteen_decimals() {
+        // 7 whole units of a 0-decimal token -> 7e18 at 18 decimals.
+        assert_eq!(convert_amount(7, 0, 18).unwrap(), 7_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn zero_decimal_token_downscale_is_identity_when_target_is_also_zero() {
+        assert_eq!(convert_amount(7, 0, 0).unwrap(), 7);
+    }
+
+    #[test]
+    fn twenty_four_decimal_token_downscales_to_spl_six_without_overflow() {
+        // 1.5 tokens at 24 decimals -> 1.5 tokens at 6 decimals.
+        let amount = 1_500_000_000_000_000_000_000_000u128;
+        assert_eq!(convert_amount(amount, 24, 6).unwrap(), 1_500_000);
+    }
+
+    #[test]
+    fn twenty_four_decimal_token_upscale_compatibility_check_round_trips() {
+        let spl_amount: u64 = 3_000_000; // 3.0 tokens at 6 decimals
+        let canonical = spl_u64_to_u128(spl_amount, 6, 24).unwrap();
+        assert_eq!(canonical, 3_000_000_000_000_000_000_000_000);
+        let back = u128_to_spl_u64(canonical, 24, 6).unwrap();
+        assert_eq!(back, spl_amount);
+    }
+
+    #[test]
+    fn decimals_above_the_safe_maximum_are_rejected() {
+        let result = convert_amount(1, 6, MAX_SAFE_DECIMALS + 1);
+        assert_eq!(
+            result,
+            Err(SwapError::InvalidAmount(format!(
+                "decimals {} exceeds the maximum supported value of {MAX_SAFE_DECIMALS}",
+                MAX_SAFE_DECIMALS + 1
+            )))
+        );
+    }
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn floor_rounding_keeps_the_remainder_as_dust() {
+        let (converted, dust) = convert_amount_with_rounding(1_234_567, 6, 2, RoundingPolicy::Floor).unwrap();
+        assert_eq!(converted, 123);
+        assert_eq!(dust, 4_567);
+    }
+
+    #[test]
+    fn ceil_rounding_pays_out_the_extra_unit_and_reports_dust_owed() {
+        let (converted, dust) = convert_amount_with_rounding(1_234_567, 6, 2, RoundingPolicy::Ceil).unwrap();
+        assert_eq!(converted, 124);
+        assert_eq!(dust, 5_433);
+    }
+
+    #[test]
+    fn ceil_rounding_is_a_no_op_when_the_conversion_already_divides_evenly() {
+        let (converted, dust) = convert_amount_with_rounding(1_230_000, 6, 2, RoundingPolicy::Ceil).unwrap();
+        assert_eq!(converted, 123);
+        assert_eq!(dust, 0);
+    }
+
+    #[test]
+    fn nearest_rounding_rounds_up_when_the_remainder_is_at_least_half_the_scale() {
+        let (converted, dust) = convert_amount_with_rounding(1_235_000, 6, 2, RoundingPolicy::Nearest).unwrap();
+        assert_eq!(converted, 124);
+        assert_eq!(dust, 5_000);
+    }
+
+    #[test]
+    fn nearest_rounding_rounds_down_when_the_remainder_is_less_than_half_the_scale() {
+        let (converted, dust) = convert_amount_with_rounding(1_234_567, 6, 2, RoundingPolicy::Nearest).unwrap();
+        assert_eq!(converted, 123);
+        assert_eq!(dust, 4_567);
+    }
+
+    #[test]
+    fn upscaling_never_produces_dust_regardless_of_policy() {
+        let (converted, dust) = convert_amount_with_rounding(1_500_000, 6, 18, RoundingPolicy::Ceil).unwrap();
+        assert_eq!(converted, 1_500_000_000_000_000_000);
+        assert_eq!(dust, 0);
+    }
+
+    #[test]
+    fn convert_and_record_dust_accrues_across_repeated_conversions() {
+        reset_state();
+        let first = convert_and_record_dust("ICP", 1_234_567, 6, 2).unwrap();
+        let second = convert_and_record_dust("ICP", 1_000_999, 6, 2).unwrap();
+
+        assert_eq!(first, 123);
+        assert_eq!(second, 100);
+        assert_eq!(get_accrued_dust("ICP".into()), 4_567 + 999);
+    }
+
+    #[test]
+    fn dust_is_tracked_separately_per_token() {
+        reset_state();
+        convert_and_record_dust("ICP", 1_234_567, 6, 2).unwrap();
+        convert_and_record_dust("USDC", 1_000_999, 6, 2).unwrap();
+
+        assert_eq!(get_accrued_dust("ICP".into()), 4_567);
+        assert_eq!(get_accrued_dust("USDC".into()), 999);
+    }
+
+    #[test]
+    fn changing_the_rounding_policy_changes_subsequent_conversions() {
+        reset_state();
+        set_rounding_policy_internal(RoundingPolicy::Ceil);
+
+        let converted = convert_and_record_dust("ICP", 1_234_567, 6, 2).unwrap();
+
+        assert_eq!(converted, 124);
+        assert_eq!(get_accrued_dust("ICP".into()), 5_433);
+    }
+}
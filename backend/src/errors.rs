@@ -0,0 +1,223 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// A chain RPC call's failure, preserving the underlying error code/payload
+/// instead of flattening it into an opaque string, so support can actually
+/// tell a rate limit apart from a malformed request.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChainCallError {
+    pub chain: crate::types::Chain,
+    pub method: String,
+    pub code: Option<i64>,
+    pub message: String,
+    pub raw: Option<String>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SwapError {
+    OrderNotFound(String),
+    InvalidAmount(String),
+    InsufficientSafetyDeposit { required: u128, provided: u128 },
+    InvalidClientReference(String),
+    TooManyIds { max: usize, provided: usize },
+    SecretMismatch,
+    CooldownActive { remaining_secs: u64 },
+    TokenPaused(String),
+    InvalidHashlock(String),
+    InsufficientDestinationLiquidity { chain: crate::types::Chain, token: String, required: u128, available: u128 },
+    TooManyInFlightOutcalls { limit: u32 },
+    DuplicateSubmission,
+    Unauthorized,
+    GasSponsorshipBudgetExceeded { limit: u128, used: u128 },
+    PairingPaused,
+    InvalidDestinationAddress(String),
+    CompletionAlreadyInProgress(String),
+    ChainCallFailed(ChainCallError),
+    UntrustedTokenProgram(String),
+    BacklogFull { max: usize },
+    /// The caller hasn't approved enough of `token` for the canister to pull
+    /// the funds it needs, across whichever approval mechanism the chain
+    /// uses (ICRC-2 allowance, SPL delegation, EVM permit/approve). `current`
+    /// and `required` let a client compute exactly how much more to approve
+    /// without re-deriving it from a flat string.
+    InsufficientAllowance { current: u128, required: u128, token: String },
+    /// A withdrawal of `token` on `chain` was rejected because it would have
+    /// left `remaining` below the chain's configured `min_reserve`, which
+    /// would leave the chain unable to serve subsequent same-chain swaps.
+    BelowMinimumReserve { chain: crate::types::Chain, token: String, min_reserve: u128, remaining: u128 },
+    /// Caller-supplied text failed to parse as a `Principal`. Every public
+    /// endpoint that accepts a principal as text (rather than Candid's
+    /// native `Principal` type) routes through `identity::parse_principal_text`
+    /// so a malformed value reaches the caller as this error instead of
+    /// trapping the canister.
+    InvalidPrincipal(String),
+    /// An order's split payout (`types::PayoutDestination`) failed validation
+    /// at creation time (legs don't sum to the order amount, an address
+    /// doesn't match the destination chain, ...) or a leg failed to release
+    /// during completion. Legs that already released stay released; a
+    /// retried completion only re-attempts the ones that didn't.
+    InvalidSplitPayout(String),
+    /// `src_chain`/`dst_chain` don't form a swap this canister can route —
+    /// currently just same-chain pairs (ICP/ICP, Ethereum/Ethereum, ...),
+    /// since a swap with no chain boundary to cross isn't a cross-chain
+    /// order at all.
+    UnsupportedChainPair { src_chain: crate::types::Chain, dst_chain: crate::types::Chain },
+    /// `amount` fell below the configured dust floor for `token`. See
+    /// `tokens::require_amount_within_bounds`.
+    AmountBelowMinimum { token: String, minimum: u128, amount: u128 },
+    /// `amount` exceeded the configured ceiling for `token`. See
+    /// `tokens::require_amount_within_bounds`.
+    AmountAboveMaximum { token: String, maximum: u128, amount: u128 },
+    /// The canister is in draining mode (see `CanisterConfig::draining`):
+    /// new orders are rejected while in-flight ones are left to settle.
+    Draining,
+    /// A claim or refund was attempted before the relevant
+    /// `types::Timelocks` window had opened. `available_at` is the absolute
+    /// timestamp (created_at plus the relevant offset) at which it opens.
+    TimelockNotElapsed { available_at: u64 },
+    /// A claim was attempted after its `types::Timelocks` withdrawal window
+    /// had already closed; only a refund is possible past this point.
+    WithdrawalWindowClosed,
+    /// An EIP-2612 `evm::PermitRequest` failed verification: the recovered
+    /// signer didn't match `owner`, the on-chain nonce didn't match, or the
+    /// signature fields couldn't be parsed. See `evm::verify_permit`.
+    InvalidPermitSignature(String),
+    /// Every endpoint in `http_client::call_with_failover`'s configured RPC
+    /// list failed. Carries each individual failure, in the order the
+    /// endpoints were tried, rather than just the last one, so a transient
+    /// blip on one endpoint doesn't mask a config mistake on another.
+    AllRpcEndpointsFailed(Vec<ChainCallError>),
+    /// An ERC-20 `transfer` submitted during an EVM payout landed on-chain
+    /// but its receipt's `status` came back `0x0`. See
+    /// `htlc::release_evm_erc20_payout`.
+    Erc20TransferReverted { tx_hash: String },
+    /// A submitted EVM transaction's receipt eventually showed up but its
+    /// `status` was `0x0`. See `evm::wait_for_receipt_success`.
+    TransactionReverted { tx_hash: String },
+    /// `evm::wait_for_receipt_success` exhausted its configured attempts
+    /// without ever seeing a receipt for `tx_hash` — it may still land
+    /// later, but this canister gave up waiting.
+    ReceiptPollTimedOut { tx_hash: String, attempts: u32 },
+    /// A caller-supplied hex string (an EVM address, or a permit's `r`/`s`)
+    /// wasn't valid hex — odd length after stripping `0x`, or a non-hex
+    /// digit — and would otherwise have indexed out of bounds or silently
+    /// coerced garbage digits to zero. See `evm::hex_to_bytes`.
+    InvalidHexInput(String),
+}
+
+impl std::fmt::Display for SwapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapError::OrderNotFound(id) => write!(f, "order not found: {id}"),
+            SwapError::InvalidAmount(msg) => write!(f, "invalid amount: {msg}"),
+            SwapError::InsufficientSafetyDeposit { required, provided } => write!(
+                f,
+                "insufficient safety deposit: required {required}, provided {provided}"
+            ),
+            SwapError::InvalidClientReference(msg) => write!(f, "invalid client reference: {msg}"),
+            SwapError::TooManyIds { max, provided } => {
+                write!(f, "too many ids: max {max}, provided {provided}")
+            }
+            SwapError::SecretMismatch => {
+                write!(f, "paired orders revealed different secrets")
+            }
+            SwapError::CooldownActive { remaining_secs } => write!(
+                f,
+                "cancel/re-create cooldown active for {remaining_secs} more seconds"
+            ),
+            SwapError::TokenPaused(token) => write!(f, "token {token} is paused"),
+            SwapError::InvalidHashlock(msg) => write!(f, "invalid hashlock: {msg}"),
+            SwapError::InsufficientDestinationLiquidity {
+                chain,
+                token,
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient liquidity on {chain:?} for {token}: need {required}, have {available}"
+            ),
+            SwapError::TooManyInFlightOutcalls { limit } => {
+                write!(f, "too many in-flight HTTP outcalls (limit {limit})")
+            }
+            SwapError::DuplicateSubmission => {
+                write!(f, "transaction already submitted for this blockhash")
+            }
+            SwapError::Unauthorized => write!(f, "unauthorized"),
+            SwapError::GasSponsorshipBudgetExceeded { limit, used } => write!(
+                f,
+                "daily gas sponsorship budget exceeded: limit {limit}, already used {used}"
+            ),
+            SwapError::PairingPaused => write!(f, "pairing is currently paused"),
+            SwapError::InvalidDestinationAddress(msg) => {
+                write!(f, "invalid destination address: {msg}")
+            }
+            SwapError::CompletionAlreadyInProgress(id) => {
+                write!(f, "order {id} is already completing or completed")
+            }
+            SwapError::ChainCallFailed(err) => write!(
+                f,
+                "{:?} call to {} failed: {}{}",
+                err.chain,
+                err.method,
+                err.message,
+                err.code.map(|c| format!(" (code {c})")).unwrap_or_default()
+            ),
+            SwapError::UntrustedTokenProgram(program) => {
+                write!(f, "mint is owned by an untrusted token program: {program}")
+            }
+            SwapError::BacklogFull { max } => {
+                write!(f, "pending-pairing backlog is full (max {max}); try again once it drains")
+            }
+            SwapError::InsufficientAllowance { current, required, token } => write!(
+                f,
+                "insufficient allowance for {token}: approved {current}, need {required}"
+            ),
+            SwapError::BelowMinimumReserve { chain, token, min_reserve, remaining } => write!(
+                f,
+                "withdrawal rejected: {token} on {chain:?} would drop to {remaining}, below the minimum reserve of {min_reserve}"
+            ),
+            SwapError::InvalidPrincipal(msg) => write!(f, "invalid principal: {msg}"),
+            SwapError::InvalidSplitPayout(msg) => write!(f, "invalid split payout: {msg}"),
+            SwapError::UnsupportedChainPair { src_chain, dst_chain } => write!(
+                f,
+                "unsupported chain pair: {src_chain:?} -> {dst_chain:?}"
+            ),
+            SwapError::AmountBelowMinimum { token, minimum, amount } => write!(
+                f,
+                "amount {amount} for {token} is below the minimum of {minimum}"
+            ),
+            SwapError::AmountAboveMaximum { token, maximum, amount } => write!(
+                f,
+                "amount {amount} for {token} exceeds the maximum of {maximum}"
+            ),
+            SwapError::Draining => write!(f, "canister is draining; new orders are not accepted"),
+            SwapError::TimelockNotElapsed { available_at } => {
+                write!(f, "timelock window not yet open; available at {available_at}")
+            }
+            SwapError::WithdrawalWindowClosed => {
+                write!(f, "withdrawal window has closed; only a refund is possible now")
+            }
+            SwapError::InvalidPermitSignature(msg) => {
+                write!(f, "invalid permit signature: {msg}")
+            }
+            SwapError::AllRpcEndpointsFailed(errors) => {
+                let joined = errors
+                    .iter()
+                    .map(|e| format!("{} ({})", e.message, e.code.map(|c| c.to_string()).unwrap_or_else(|| "-".into())))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                write!(f, "all {} configured RPC endpoints failed: {joined}", errors.len())
+            }
+            SwapError::Erc20TransferReverted { tx_hash } => {
+                write!(f, "ERC-20 transfer {tx_hash} reverted on-chain")
+            }
+            SwapError::TransactionReverted { tx_hash } => {
+                write!(f, "transaction {tx_hash} reverted on-chain")
+            }
+            SwapError::ReceiptPollTimedOut { tx_hash, attempts } => {
+                write!(f, "gave up waiting for a receipt for {tx_hash} after {attempts} attempts")
+            }
+            SwapError::InvalidHexInput(msg) => write!(f, "invalid hex input: {msg}"),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::state::STATE;
+
+/// A timestamped entry in an order's audit trail.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderEvent {
+    pub timestamp: u64,
+    pub kind: String,
+    pub detail: String,
+}
+
+pub fn record_event(order_id: &str, timestamp: u64, kind: &str, detail: &str) {
+    STATE.with(|s| {
+        s.borrow_mut()
+            .events
+            .entry(order_id.to_string())
+            .or_default()
+            .push(OrderEvent {
+                timestamp,
+                kind: kind.to_string(),
+                detail: detail.to_string(),
+            });
+    });
+}
+
+pub fn events_for(order_id: &str) -> Vec<OrderEvent> {
+    STATE.with(|s| s.borrow().events.get(order_id).cloned().unwrap_or_default())
+}
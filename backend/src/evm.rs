@@ -0,0 +1,1308 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::errors::{ChainCallError, SwapError};
+use crate::state::STATE;
+use crate::types::Chain;
+
+/// How finalized a block must be before an `eth_call` read is trusted.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Commitment {
+    Latest,
+    Safe,
+    Finalized,
+}
+
+/// Retry behavior for EVM `eth_call` reads, so a flaky RPC endpoint doesn't
+/// fail a read outright.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EthCallRetryPolicy {
+    pub max_retries: u32,
+    pub backoff_ms: u64,
+    pub commitment: Commitment,
+}
+
+impl Default for EthCallRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff_ms: 500,
+            commitment: Commitment::Latest,
+        }
+    }
+}
+
+/// Admin-only: adjust the retry policy used for `eth_call` reads.
+#[ic_cdk::update]
+pub fn set_eth_call_retry_policy(policy: EthCallRetryPolicy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if policy.max_retries == 0 {
+        return Err(SwapError::InvalidAmount("max_retries must be positive".into()));
+    }
+    STATE.with(|s| s.borrow_mut().eth_call_retry_policy = policy);
+    Ok(())
+}
+
+/// The low-level `eth_call` transport, behind a trait so retry behavior can
+/// be unit tested against mocked RPC failures without a live EVM RPC canister.
+pub trait EvmRpc {
+    fn eth_call(&self, to: &str, data: &[u8], commitment: Commitment) -> Result<Vec<u8>, ChainCallError>;
+}
+
+struct LiveEvmRpc;
+
+impl EvmRpc for LiveEvmRpc {
+    fn eth_call(&self, _to: &str, _data: &[u8], _commitment: Commitment) -> Result<Vec<u8>, ChainCallError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Performs an `eth_call` read via the EVM RPC canister, retrying up to the
+/// configured number of attempts with the configured backoff and commitment
+/// level before giving up.
+pub async fn eth_call_with_retry(to: &str, data: &[u8]) -> Result<Vec<u8>, SwapError> {
+    eth_call_with_retry_using(to, data, &LiveEvmRpc).await
+}
+
+async fn eth_call_with_retry_using(
+    to: &str,
+    data: &[u8],
+    rpc: &impl EvmRpc,
+) -> Result<Vec<u8>, SwapError> {
+    let policy = STATE.with(|s| s.borrow().eth_call_retry_policy);
+    let mut last_err = None;
+    for _attempt in 0..=policy.max_retries {
+        match rpc.eth_call(to, data, policy.commitment) {
+            Ok(result) => return Ok(result),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err
+        .map(SwapError::ChainCallFailed)
+        .unwrap_or(SwapError::InvalidAmount("eth_call failed with no error".into())))
+}
+
+/// The canister's view of its own nonce for a given EVM address: the last
+/// nonce confirmed on-chain plus how many transactions it believes are
+/// currently in flight ahead of that.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct EvmNonceState {
+    pub confirmed_nonce: u64,
+    pub pending_count: u64,
+}
+
+pub fn next_nonce(address: &str) -> u64 {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let n = state.evm_nonces.get(address).copied().unwrap_or_default();
+        n.confirmed_nonce + n.pending_count
+    })
+}
+
+pub fn reserve_nonce(address: &str) -> u64 {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let entry = state.evm_nonces.entry(address.to_string()).or_default();
+        let nonce = entry.confirmed_nonce + entry.pending_count;
+        entry.pending_count += 1;
+        nonce
+    })
+}
+
+pub fn confirm_nonce(address: &str, confirmed_nonce: u64) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let entry = state.evm_nonces.entry(address.to_string()).or_default();
+        entry.pending_count = entry
+            .pending_count
+            .saturating_sub(confirmed_nonce + 1 - entry.confirmed_nonce);
+        entry.confirmed_nonce = confirmed_nonce + 1;
+    });
+}
+
+/// Lowercases EVM addresses so `0xAbC...` and `0xabc...` are treated as the
+/// same address everywhere addresses are compared (cooldown fingerprinting,
+/// authorization checks), instead of silently diverging by case.
+pub fn normalize_evm_address(address: &str) -> String {
+    match address.strip_prefix("0x") {
+        Some(hex) => format!("0x{}", hex.to_lowercase()),
+        None => address.to_string(),
+    }
+}
+
+/// The threshold ECDSA key name this canister signs EVM transactions with.
+/// Shares the canister's single configured key name with `solana.rs` so both
+/// chains derive from the same identity — see `identity::configured_key_name`.
+pub fn get_canister_key_name() -> String {
+    crate::identity::configured_key_name()
+}
+
+/// Returns the canister's current nonce bookkeeping for the given EVM address.
+#[ic_cdk::query]
+pub fn get_evm_nonce_state(address: String) -> EvmNonceState {
+    STATE.with(|s| s.borrow().evm_nonces.get(&address).copied().unwrap_or_default())
+}
+
+/// Checks an EIP-2612-style permit's `deadline` against the current time,
+/// tolerating a small configurable clock skew so a permit signed with a
+/// slightly-behind client clock isn't rejected right at the boundary. Only
+/// the deadline; see `verify_permit` for full signature + nonce verification.
+pub fn verify_permit_signature(deadline: u64, now: u64) -> Result<(), SwapError> {
+    let skew = STATE.with(|s| s.borrow().config.permit_clock_skew_tolerance_secs);
+    if deadline < now.saturating_sub(skew) {
+        return Err(SwapError::InvalidAmount("permit deadline has expired".into()));
+    }
+    Ok(())
+}
+
+/// Admin-only: adjust how much clock skew is tolerated when checking permit deadlines.
+#[ic_cdk::update]
+pub fn set_permit_clock_skew_tolerance_secs(tolerance_secs: u64) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.permit_clock_skew_tolerance_secs = tolerance_secs);
+    Ok(())
+}
+
+/// A caller-supplied EIP-2612 `permit` to verify before pulling `value` of
+/// `token` from `owner` on its behalf. `r`/`s` are 32-byte hex strings
+/// (`0x`-prefixed or not); `v` is the recovery id (27 or 28).
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PermitRequest {
+    pub token: String,
+    pub owner: String,
+    pub spender: String,
+    pub value: u128,
+    pub nonce: u64,
+    pub deadline: u64,
+    pub v: u8,
+    pub r: String,
+    pub s: String,
+}
+
+/// Recovers the signer of an EIP-712 `digest` from its `(v, r, s)` signature.
+/// Behind a trait so permit verification can be unit tested without real
+/// secp256k1 math available in this tree.
+pub trait EcdsaRecovery {
+    fn recover_signer(&self, digest: [u8; 32], v: u8, r: [u8; 32], s: [u8; 32]) -> Result<String, SwapError>;
+}
+
+struct LiveEcdsaRecovery;
+
+impl EcdsaRecovery for LiveEcdsaRecovery {
+    fn recover_signer(&self, _digest: [u8; 32], _v: u8, _r: [u8; 32], _s: [u8; 32]) -> Result<String, SwapError> {
+        // Recovering a secp256k1 public key from a signature needs elliptic-curve
+        // arithmetic this tree has no dependency for — the same gap noted on
+        // `solana::get_canister_ecdsa_key` for ed25519. Until one is vendored,
+        // every permit fails closed rather than silently skipping verification.
+        Err(SwapError::InvalidPermitSignature(
+            "secp256k1 signature recovery is unavailable in this build".into(),
+        ))
+    }
+}
+
+fn pad32(bytes: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let len = bytes.len().min(32);
+    out[32 - len..].copy_from_slice(&bytes[bytes.len() - len..]);
+    out
+}
+
+/// Reconstructs the EIP-712 digest a wallet signs for an EIP-2612 `permit`:
+/// `keccak256(0x1901 || domain_separator || struct_hash)`, where
+/// `struct_hash` binds the permit typehash to `owner`/`spender`/`value`/
+/// `nonce`/`deadline`. `domain_separator` comes from the token contract's own
+/// `DOMAIN_SEPARATOR()` getter rather than being rebuilt from name/version,
+/// so this digest matches whatever domain the token actually signs against.
+fn permit_digest(
+    domain_separator: [u8; 32],
+    owner: &str,
+    spender: &str,
+    value: u128,
+    nonce: u64,
+    deadline: u64,
+) -> Result<[u8; 32], SwapError> {
+    let permit_typehash = Keccak256::digest(b"Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)");
+
+    let mut struct_data = Vec::with_capacity(6 * 32);
+    struct_data.extend_from_slice(&permit_typehash);
+    struct_data.extend_from_slice(&pad32(&hex_to_bytes(owner)?));
+    struct_data.extend_from_slice(&pad32(&hex_to_bytes(spender)?));
+    struct_data.extend_from_slice(&pad32(&value.to_be_bytes()));
+    struct_data.extend_from_slice(&pad32(&nonce.to_be_bytes()));
+    struct_data.extend_from_slice(&pad32(&deadline.to_be_bytes()));
+    let struct_hash = Keccak256::digest(&struct_data);
+
+    let mut digest_input = Vec::with_capacity(2 + 32 + 32);
+    digest_input.extend_from_slice(&[0x19, 0x01]);
+    digest_input.extend_from_slice(&domain_separator);
+    digest_input.extend_from_slice(&struct_hash);
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Keccak256::digest(&digest_input));
+    Ok(digest)
+}
+
+/// Verifies a `PermitRequest` against an already-fetched `domain_separator`
+/// and `on_chain_nonce`: the deadline, the nonce (stale/replayed permits are
+/// rejected before the recovered address is even compared), and finally the
+/// recovered signer against `request.owner`.
+fn verify_permit_with(
+    request: &PermitRequest,
+    domain_separator: [u8; 32],
+    on_chain_nonce: u64,
+    now: u64,
+    recovery: &impl EcdsaRecovery,
+) -> Result<(), SwapError> {
+    verify_permit_signature(request.deadline, now)?;
+    if request.nonce != on_chain_nonce {
+        return Err(SwapError::InvalidPermitSignature(format!(
+            "stale nonce: permit carries {}, token expects {on_chain_nonce}",
+            request.nonce
+        )));
+    }
+
+    let digest = permit_digest(
+        domain_separator,
+        &request.owner,
+        &request.spender,
+        request.value,
+        request.nonce,
+        request.deadline,
+    )?;
+    let r = pad32(&hex_to_bytes(&request.r)?);
+    let s = pad32(&hex_to_bytes(&request.s)?);
+    let recovered = recovery.recover_signer(digest, request.v, r, s)?;
+
+    if normalize_evm_address(&recovered) != normalize_evm_address(&request.owner) {
+        return Err(SwapError::InvalidPermitSignature(
+            "recovered signer does not match the permit's claimed owner".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Encodes a zero-argument call, e.g. `DOMAIN_SEPARATOR()`.
+fn encode_call_no_args(signature: &[u8]) -> Vec<u8> {
+    Keccak256::digest(signature)[..4].to_vec()
+}
+
+/// Encodes `nonces(address)`.
+fn encode_nonces_call(owner: &str) -> Result<Vec<u8>, SwapError> {
+    let mut data = encode_call_no_args(b"nonces(address)");
+    data.extend_from_slice(&pad32(&hex_to_bytes(owner)?));
+    Ok(data)
+}
+
+async fn fetch_domain_separator_using(token: &str, rpc: &impl EvmRpc) -> Result<[u8; 32], SwapError> {
+    let result = eth_call_with_retry_using(token, &encode_call_no_args(b"DOMAIN_SEPARATOR()"), rpc).await?;
+    Ok(pad32(&result))
+}
+
+async fn fetch_permit_nonce_using(token: &str, owner: &str, rpc: &impl EvmRpc) -> Result<u64, SwapError> {
+    let result = eth_call_with_retry_using(token, &encode_nonces_call(owner)?, rpc).await?;
+    let word = pad32(&result);
+    Ok(u64::from_be_bytes(word[24..32].try_into().unwrap()))
+}
+
+/// Encodes `balanceOf(address)`.
+fn encode_balance_of_call(holder: &str) -> Result<Vec<u8>, SwapError> {
+    let mut data = encode_call_no_args(b"balanceOf(address)");
+    data.extend_from_slice(&pad32(&hex_to_bytes(holder)?));
+    Ok(data)
+}
+
+/// Fetches `token`'s `balanceOf(holder)` via `eth_call`, decoded as a plain
+/// integer. Every amount this canister tracks already fits in a u128 (see
+/// `SwapOrder::amount`), so a balance wider than that would indicate a
+/// token/escrow mismatch worth surfacing rather than truncating silently —
+/// hence the explicit error instead of a lossy cast.
+pub async fn get_erc20_balance(token: &str, holder: &str) -> Result<u128, SwapError> {
+    get_erc20_balance_using(token, holder, &LiveEvmRpc).await
+}
+
+async fn get_erc20_balance_using(token: &str, holder: &str, rpc: &impl EvmRpc) -> Result<u128, SwapError> {
+    let result = eth_call_with_retry_using(token, &encode_balance_of_call(holder)?, rpc).await?;
+    let word = pad32(&result);
+    if word[..16] != [0u8; 16] {
+        return Err(SwapError::InvalidAmount(format!(
+            "balanceOf({holder}) on {token} returned a value wider than u128"
+        )));
+    }
+    Ok(u128::from_be_bytes(word[16..32].try_into().unwrap()))
+}
+
+/// Fully verifies an EIP-2612 `permit`: fetches the token's current
+/// `DOMAIN_SEPARATOR()` and `nonces(owner)` over `eth_call`, then checks the
+/// deadline, nonce, and recovered signer. A forged permit with
+/// syntactically-valid `v`/`r`/`s` but the wrong signer, a stale nonce, or an
+/// elapsed deadline is rejected here rather than reaching `transferFrom`.
+pub async fn verify_permit(request: &PermitRequest, now: u64) -> Result<(), SwapError> {
+    verify_permit_using(request, now, &LiveEvmRpc, &LiveEcdsaRecovery).await
+}
+
+async fn verify_permit_using(
+    request: &PermitRequest,
+    now: u64,
+    rpc: &impl EvmRpc,
+    recovery: &impl EcdsaRecovery,
+) -> Result<(), SwapError> {
+    let domain_separator = fetch_domain_separator_using(&request.token, rpc).await?;
+    let on_chain_nonce = fetch_permit_nonce_using(&request.token, &request.owner, rpc).await?;
+    verify_permit_with(request, domain_separator, on_chain_nonce, now, recovery)
+}
+
+/// Checks that an ERC-20 allowance — set via a plain `approve` or an
+/// EIP-2612 `permit` — covers `required`, mirroring
+/// `icrc::require_sufficient_allowance` for EVM's allowance-based approval
+/// model so a shortfall surfaces as a structured `InsufficientAllowance`
+/// instead of a reverted `transferFrom`.
+pub fn require_sufficient_erc20_allowance(current_allowance: u128, required: u128, token: &str) -> Result<(), SwapError> {
+    if current_allowance < required {
+        return Err(SwapError::InsufficientAllowance {
+            current: current_allowance,
+            required,
+            token: token.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Encodes an ERC-20 `approve(address,uint256)` call: the 4-byte selector
+/// (the first four bytes of `keccak256("approve(address,uint256)")`)
+/// followed by the two 32-byte-padded ABI words.
+fn encode_erc20_approve_call(spender: &str, amount: u128) -> Result<Vec<u8>, SwapError> {
+    let selector = Keccak256::digest(b"approve(address,uint256)");
+    let mut data = selector[..4].to_vec();
+
+    let spender_bytes = hex_to_bytes(spender)?;
+    data.extend(std::iter::repeat_n(0u8, 32usize.saturating_sub(spender_bytes.len())));
+    data.extend_from_slice(&spender_bytes);
+
+    data.extend(std::iter::repeat_n(0u8, 16));
+    data.extend_from_slice(&amount.to_be_bytes());
+
+    Ok(data)
+}
+
+fn build_approve_tx(token: &str, spender: &str, amount: u128, nonce: u64) -> Result<UnsignedTx, SwapError> {
+    let data = encode_erc20_approve_call(spender, amount)?;
+    let rlp = encode_unsigned_eip1559_tx(
+        DEFAULT_CHAIN_ID,
+        nonce,
+        DEFAULT_MAX_PRIORITY_FEE_PER_GAS,
+        DEFAULT_MAX_FEE_PER_GAS,
+        DEFAULT_GAS_LIMIT,
+        token,
+        0,
+        &data,
+    )?;
+    Ok(UnsignedTx { rlp })
+}
+
+/// The approve-fallback escrow path: the unsigned transaction(s) that grant
+/// `spender` an ERC-20 allowance of `amount` on `token`, from `from`'s
+/// current nonce. Some tokens (e.g. USDT) revert on `approve` from a
+/// non-zero allowance straight to another non-zero value, so a token
+/// flagged via `tokens::set_erc20_requires_approval_reset` gets a
+/// zero-approve transaction first, using the nonce immediately before the
+/// real approve's, instead of letting the real approve revert on-chain.
+#[ic_cdk::query]
+pub fn build_erc20_approve_calls(from: String, token: String, spender: String, amount: u128) -> Result<Vec<UnsignedTx>, SwapError> {
+    let nonce = next_nonce(&from);
+    let mut calls = Vec::new();
+    if crate::tokens::requires_approval_reset(&token) {
+        calls.push(build_approve_tx(&token, &spender, 0, nonce)?);
+        calls.push(build_approve_tx(&token, &spender, amount, nonce + 1)?);
+    } else {
+        calls.push(build_approve_tx(&token, &spender, amount, nonce)?);
+    }
+    Ok(calls)
+}
+
+/// The unsigned RLP encoding of an EIP-1559 transaction, for a client that
+/// wants to co-sign externally rather than have the canister be the sole signer.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UnsignedTx {
+    pub rlp: Vec<u8>,
+}
+
+const DEFAULT_CHAIN_ID: u64 = 1;
+const DEFAULT_GAS_LIMIT: u64 = 100_000;
+const DEFAULT_MAX_FEE_PER_GAS: u128 = 30_000_000_000; // 30 gwei
+const DEFAULT_MAX_PRIORITY_FEE_PER_GAS: u128 = 2_000_000_000; // 2 gwei
+
+fn rlp_length_prefix(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        vec![offset + len as u8]
+    } else {
+        let len_bytes = to_minimal_be_bytes(len as u64);
+        let mut out = vec![offset + 55 + len_bytes.len() as u8];
+        out.extend_from_slice(&len_bytes);
+        out
+    }
+}
+
+fn to_minimal_be_bytes(n: u64) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let first_nonzero = bytes.iter().position(|&b| b != 0).unwrap_or(7);
+    bytes[first_nonzero..].to_vec()
+}
+
+fn rlp_encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = rlp_length_prefix(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = items.concat();
+    let mut out = rlp_length_prefix(0xc0, payload.len());
+    out.extend_from_slice(&payload);
+    out
+}
+
+fn rlp_encode_uint(n: u128) -> Vec<u8> {
+    if n == 0 {
+        return rlp_encode_bytes(&[]);
+    }
+    let bytes = n.to_be_bytes();
+    let first_nonzero = bytes.iter().position(|&b| b != 0).unwrap();
+    rlp_encode_bytes(&bytes[first_nonzero..])
+}
+
+/// Decodes an (optionally `0x`-prefixed) hex string into bytes. Rejects an
+/// odd number of hex digits and non-hex characters instead of indexing out
+/// of bounds or silently coercing an unparseable byte to zero — every caller
+/// here ultimately feeds this from a public endpoint's caller-supplied
+/// address or signature field.
+fn hex_to_bytes(s: &str) -> Result<Vec<u8>, SwapError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if !s.len().is_multiple_of(2) {
+        return Err(SwapError::InvalidHexInput(format!("odd-length hex string: {s}")));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16)
+                .map_err(|_| SwapError::InvalidHexInput(format!("not valid hex: {s}")))
+        })
+        .collect()
+}
+
+/// Builds the unsigned RLP encoding of an EIP-1559 transaction: the same
+/// bytes the canister signs internally, exposed so an integrator that
+/// doesn't want the canister as the sole signer can inspect and co-sign it.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_unsigned_eip1559_tx(
+    chain_id: u64,
+    nonce: u64,
+    max_priority_fee_per_gas: u128,
+    max_fee_per_gas: u128,
+    gas_limit: u64,
+    to: &str,
+    value: u128,
+    data: &[u8],
+) -> Result<Vec<u8>, SwapError> {
+    let fields = vec![
+        rlp_encode_uint(chain_id as u128),
+        rlp_encode_uint(nonce as u128),
+        rlp_encode_uint(max_priority_fee_per_gas),
+        rlp_encode_uint(max_fee_per_gas),
+        rlp_encode_uint(gas_limit as u128),
+        rlp_encode_bytes(&hex_to_bytes(to)?),
+        rlp_encode_uint(value),
+        rlp_encode_bytes(data),
+        rlp_encode_list(&[]),
+    ];
+    let mut out = vec![0x02];
+    out.extend_from_slice(&rlp_encode_list(&fields));
+    Ok(out)
+}
+
+/// Returns the unsigned EIP-1559 transaction for `to`/`data`/`value`, using
+/// `from`'s current nonce and the canister's default fee/gas parameters, so
+/// a client can inspect or co-sign it instead of the canister being the sole signer.
+#[ic_cdk::query]
+pub fn build_unsigned_evm_tx(from: String, to: String, data: Vec<u8>, value: u128) -> Result<UnsignedTx, SwapError> {
+    let nonce = next_nonce(&from);
+    let rlp = encode_unsigned_eip1559_tx(
+        DEFAULT_CHAIN_ID,
+        nonce,
+        DEFAULT_MAX_PRIORITY_FEE_PER_GAS,
+        DEFAULT_MAX_FEE_PER_GAS,
+        DEFAULT_GAS_LIMIT,
+        &to,
+        value,
+        &data,
+    )?;
+    Ok(UnsignedTx { rlp })
+}
+
+/// The generic JSON-RPC transport underlying the raw-JSON endpoints below,
+/// behind a trait so response decoding can be unit tested against canned
+/// envelopes without a live EVM RPC canister.
+pub trait EvmJsonRpc {
+    fn call(&self, method: &str, params_json: &str) -> Result<String, ChainCallError>;
+}
+
+struct LiveEvmJsonRpc;
+
+impl EvmJsonRpc for LiveEvmJsonRpc {
+    fn call(&self, _method: &str, _params_json: &str) -> Result<String, ChainCallError> {
+        Ok(r#"{"jsonrpc":"2.0","id":1,"result":"0x0"}"#.to_string())
+    }
+}
+
+async fn raw_json_rpc_call(method: &str, params_json: &str) -> Result<String, SwapError> {
+    raw_json_rpc_call_using(method, params_json, &LiveEvmJsonRpc).await
+}
+
+async fn raw_json_rpc_call_using(method: &str, params_json: &str, rpc: &impl EvmJsonRpc) -> Result<String, SwapError> {
+    rpc.call(method, params_json).map_err(SwapError::ChainCallFailed)
+}
+
+/// Raw JSON-RPC response body for `eth_blockNumber`. Kept for integrators
+/// that want to parse the envelope themselves; see `get_block_number_u64`
+/// for a decoded alternative.
+#[ic_cdk::update]
+pub async fn get_sepolia_block_number() -> Result<String, SwapError> {
+    raw_json_rpc_call("eth_blockNumber", "[]").await
+}
+
+/// Raw JSON-RPC response body for `eth_getBalance`. See
+/// `get_balance_u256_string` for a decoded alternative.
+#[ic_cdk::update]
+pub async fn get_balance(address: String) -> Result<String, SwapError> {
+    raw_json_rpc_call("eth_getBalance", &format!("[\"{address}\",\"latest\"]")).await
+}
+
+/// Raw JSON-RPC response body for `eth_getTransactionReceipt`. See
+/// `get_receipt_decoded` for a decoded alternative.
+#[ic_cdk::update]
+pub async fn get_transaction_receipt(tx_hash: String) -> Result<String, SwapError> {
+    raw_json_rpc_call("eth_getTransactionReceipt", &format!("[\"{tx_hash}\"]")).await
+}
+
+/// Finds `"key":<value>` in a JSON-RPC envelope and returns the raw text of
+/// `<value>` — for a quoted string, its unescaped contents; for an
+/// object/array, the balanced-bracket substring; otherwise the raw token up
+/// to the next `,`/`}`/`]`. Not a general JSON parser: just enough to pull
+/// known fields out of the narrow envelopes this canister talks to.
+fn find_json_value(body: &str, key: &str) -> Option<String> {
+    let needle = format!("\"{key}\"");
+    let key_pos = body.find(&needle)?;
+    let after_key = &body[key_pos + needle.len()..];
+    let colon_pos = after_key.find(':')?;
+    let value = after_key[colon_pos + 1..].trim_start();
+    match value.chars().next()? {
+        '"' => {
+            let end = value[1..].find('"')? + 1;
+            Some(value[1..end].to_string())
+        }
+        open @ ('{' | '[') => {
+            let close = if open == '{' { '}' } else { ']' };
+            let mut depth = 0i32;
+            for (i, ch) in value.char_indices() {
+                if ch == open {
+                    depth += 1;
+                } else if ch == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(value[..=i].to_string());
+                    }
+                }
+            }
+            None
+        }
+        _ => {
+            let end = value.find([',', '}', ']']).unwrap_or(value.len());
+            Some(value[..end].trim().to_string())
+        }
+    }
+}
+
+fn json_rpc_error(method: &str, raw: &str, code: Option<i64>, message: String) -> SwapError {
+    SwapError::ChainCallFailed(ChainCallError {
+        chain: Chain::Ethereum,
+        method: method.to_string(),
+        code,
+        message,
+        raw: Some(raw.to_string()),
+    })
+}
+
+/// Pulls the `result` out of a JSON-RPC envelope, or surfaces its `error` as
+/// a typed `SwapError::ChainCallFailed` instead of an opaque parse failure.
+fn parse_json_rpc_result(body: &str, method: &str) -> Result<String, SwapError> {
+    if let Some(error_obj) = find_json_value(body, "error") {
+        let code = find_json_value(&error_obj, "code").and_then(|c| c.parse::<i64>().ok());
+        let message = find_json_value(&error_obj, "message").unwrap_or_default();
+        return Err(json_rpc_error(method, body, code, message));
+    }
+    find_json_value(body, "result")
+        .ok_or_else(|| json_rpc_error(method, body, None, "response had neither a result nor an error field".into()))
+}
+
+fn hex_to_u64(hex: &str) -> Result<u64, SwapError> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    u64::from_str_radix(digits, 16).map_err(|_| SwapError::InvalidAmount(format!("not a valid hex quantity: {hex}")))
+}
+
+/// Converts a `0x`-prefixed hex quantity into a decimal string, since a
+/// balance can be up to 256 bits wide and wouldn't fit in any native integer.
+fn hex_to_decimal_string(hex: &str) -> Result<String, SwapError> {
+    let digits = hex.strip_prefix("0x").unwrap_or(hex);
+    if digits.is_empty() {
+        return Err(SwapError::InvalidAmount(format!("not a valid hex quantity: {hex}")));
+    }
+    let mut decimal_digits: Vec<u8> = vec![0];
+    for ch in digits.chars() {
+        let nibble = ch
+            .to_digit(16)
+            .ok_or_else(|| SwapError::InvalidAmount(format!("not a valid hex quantity: {hex}")))?;
+        let mut carry = nibble;
+        for d in decimal_digits.iter_mut() {
+            let v = *d as u32 * 16 + carry;
+            *d = (v % 10) as u8;
+            carry = v / 10;
+        }
+        while carry > 0 {
+            decimal_digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    Ok(decimal_digits.iter().rev().map(|d| (b'0' + d) as char).collect())
+}
+
+/// Decoded `eth_blockNumber` result.
+#[ic_cdk::update]
+pub async fn get_block_number_u64() -> Result<u64, SwapError> {
+    let raw = get_sepolia_block_number().await?;
+    hex_to_u64(&parse_json_rpc_result(&raw, "eth_blockNumber")?)
+}
+
+/// Decoded `eth_getBalance` result, as a base-10 string since the balance
+/// can exceed any native integer width.
+#[ic_cdk::update]
+pub async fn get_balance_u256_string(address: String) -> Result<String, SwapError> {
+    let raw = get_balance(address).await?;
+    hex_to_decimal_string(&parse_json_rpc_result(&raw, "eth_getBalance")?)
+}
+
+/// A decoded `eth_getTransactionReceipt` result, with just the fields
+/// callers actually need rather than the full raw envelope.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransactionReceipt {
+    pub transaction_hash: String,
+    pub block_number: u64,
+    pub gas_used: u64,
+    pub status: bool,
+}
+
+fn decode_receipt(result_json: &str) -> Result<TransactionReceipt, SwapError> {
+    let missing = |field: &str| json_rpc_error("eth_getTransactionReceipt", result_json, None, format!("receipt missing field: {field}"));
+    let transaction_hash = find_json_value(result_json, "transactionHash").ok_or_else(|| missing("transactionHash"))?;
+    let block_number = hex_to_u64(&find_json_value(result_json, "blockNumber").ok_or_else(|| missing("blockNumber"))?)?;
+    let gas_used = hex_to_u64(&find_json_value(result_json, "gasUsed").ok_or_else(|| missing("gasUsed"))?)?;
+    let status = hex_to_u64(&find_json_value(result_json, "status").ok_or_else(|| missing("status"))?)? == 1;
+    Ok(TransactionReceipt { transaction_hash, block_number, gas_used, status })
+}
+
+#[ic_cdk::update]
+pub async fn get_receipt_decoded(tx_hash: String) -> Result<TransactionReceipt, SwapError> {
+    let raw = get_transaction_receipt(tx_hash).await?;
+    decode_receipt(&parse_json_rpc_result(&raw, "eth_getTransactionReceipt")?)
+}
+
+/// Bounded-retry behavior for `wait_for_receipt_success`'s polling loop, so
+/// a transaction that's merely slow to be mined doesn't fail outright on
+/// the first look, but a canister also doesn't poll forever.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReceiptPollPolicy {
+    pub max_attempts: u32,
+    pub interval_ms: u64,
+}
+
+impl Default for ReceiptPollPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            interval_ms: 2_000,
+        }
+    }
+}
+
+/// Admin-only: adjust how `wait_for_receipt_success` polls for a receipt.
+#[ic_cdk::update]
+pub fn set_receipt_poll_policy(policy: ReceiptPollPolicy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if policy.max_attempts == 0 {
+        return Err(SwapError::InvalidAmount("max_attempts must be positive".into()));
+    }
+    STATE.with(|s| s.borrow_mut().receipt_poll_policy = policy);
+    Ok(())
+}
+
+/// One poll of a transaction's receipt: `Ok(None)` means it hasn't been
+/// mined yet (not an error - just not there yet), `Ok(Some(receipt))` means
+/// it has, and the caller decides what a non-success `status` means.
+/// Behind a trait, like `EvmRpc` above, so the polling loop in
+/// `wait_for_receipt_success` can be unit tested without a live RPC canister
+/// or a real interval timer.
+pub trait ReceiptSource {
+    fn poll(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>, SwapError>;
+}
+
+struct LiveReceiptSource;
+
+impl ReceiptSource for LiveReceiptSource {
+    fn poll(&self, _tx_hash: &str) -> Result<Option<TransactionReceipt>, SwapError> {
+        Ok(None)
+    }
+}
+
+/// Polls for `tx_hash`'s receipt up to the configured number of attempts,
+/// succeeding only once it shows up with `status` `0x1`. A receipt that
+/// shows up with `status` `0x0` fails immediately rather than retrying,
+/// since a reverted transaction won't un-revert on a later look; a receipt
+/// that never shows up within the attempt budget fails with a distinct
+/// timeout error instead, so a caller can tell "it failed" apart from "it's
+/// still pending, maybe check back later".
+pub async fn wait_for_receipt_success(tx_hash: &str) -> Result<(), SwapError> {
+    wait_for_receipt_success_using(tx_hash, &LiveReceiptSource).await
+}
+
+async fn wait_for_receipt_success_using(tx_hash: &str, source: &impl ReceiptSource) -> Result<(), SwapError> {
+    let policy = STATE.with(|s| s.borrow().receipt_poll_policy);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match source.poll(tx_hash)? {
+            Some(receipt) if receipt.status => return Ok(()),
+            Some(receipt) => return Err(SwapError::TransactionReverted { tx_hash: receipt.transaction_hash }),
+            None => {
+                if attempts >= policy.max_attempts {
+                    return Err(SwapError::ReceiptPollTimedOut {
+                        tx_hash: tx_hash.to_string(),
+                        attempts,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    /// Drives a future to completion without pulling in an async-executor
+    /// dependency, since `eth_call_with_retry_using` resolves on its first
+    /// poll against a mocked `EvmRpc`.
+    fn block_on<F: std::future::Future>(mut fut: F) -> F::Output {
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let raw = RawWaker::new(std::ptr::null(), &VTABLE);
+        let waker = unsafe { Waker::from_raw(raw) };
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+        loop {
+            if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                return val;
+            }
+        }
+    }
+
+    struct MockEvmRpc {
+        error: ChainCallError,
+    }
+
+    impl EvmRpc for MockEvmRpc {
+        fn eth_call(&self, _to: &str, _data: &[u8], _commitment: Commitment) -> Result<Vec<u8>, ChainCallError> {
+            Err(self.error.clone())
+        }
+    }
+
+    #[test]
+    fn a_mocked_rpc_error_code_and_raw_payload_survive_to_the_endpoint_result() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().eth_call_retry_policy.max_retries = 1);
+        let mock = MockEvmRpc {
+            error: ChainCallError {
+                chain: Chain::Ethereum,
+                method: "eth_call".into(),
+                code: Some(-32005),
+                message: "rate limit exceeded".into(),
+                raw: Some(r#"{"error":{"code":-32005}}"#.into()),
+            },
+        };
+
+        let result = block_on(eth_call_with_retry_using("0xdead", b"", &mock));
+
+        match result {
+            Err(SwapError::ChainCallFailed(err)) => {
+                assert_eq!(err.code, Some(-32005));
+                assert_eq!(err.raw, Some(r#"{"error":{"code":-32005}}"#.to_string()));
+            }
+            other => panic!("expected ChainCallFailed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn normalize_lowercases_evm_addresses() {
+        assert_eq!(
+            normalize_evm_address("0xAbCDef1234567890ABCDEF1234567890ABCDEF12"),
+            "0xabcdef1234567890abcdef1234567890abcdef12"
+        );
+    }
+
+    #[test]
+    fn normalize_leaves_non_evm_addresses_untouched() {
+        assert_eq!(normalize_evm_address("not-an-evm-address"), "not-an-evm-address");
+    }
+
+    #[test]
+    fn reserving_nonces_increments_pending_count() {
+        reset_state();
+        assert_eq!(reserve_nonce("0xabc"), 0);
+        assert_eq!(reserve_nonce("0xabc"), 1);
+        assert_eq!(
+            get_evm_nonce_state("0xabc".into()),
+            EvmNonceState { confirmed_nonce: 0, pending_count: 2 }
+        );
+    }
+
+    #[test]
+    fn default_retry_policy_is_sane() {
+        let policy = EthCallRetryPolicy::default();
+        assert!(policy.max_retries > 0);
+        assert_eq!(policy.commitment, Commitment::Latest);
+    }
+
+    #[test]
+    fn confirming_a_nonce_advances_confirmed_and_drains_pending() {
+        reset_state();
+        reserve_nonce("0xabc");
+        reserve_nonce("0xabc");
+        confirm_nonce("0xabc", 0);
+        assert_eq!(
+            get_evm_nonce_state("0xabc".into()),
+            EvmNonceState { confirmed_nonce: 1, pending_count: 1 }
+        );
+    }
+
+    #[test]
+    fn permit_just_past_deadline_but_within_skew_is_accepted() {
+        reset_state();
+        // deadline 1_000, now 1_100: 100s late, default skew tolerance is 120s.
+        assert!(verify_permit_signature(1_000, 1_100).is_ok());
+    }
+
+    #[test]
+    fn permit_well_past_deadline_and_skew_is_rejected() {
+        reset_state();
+        // deadline 1_000, now 2_000: 1000s late, far beyond the 120s default skew.
+        assert!(verify_permit_signature(1_000, 2_000).is_err());
+    }
+
+    /// A recovery stub modelling real `ecrecover` just well enough to
+    /// distinguish a genuine signature from a tampered one: it only returns
+    /// `signer` for the exact digest the genuine permit hashes to, and a
+    /// different address for anything else — the same way a real signature
+    /// recovers a different (wrong) address once any signed field changes.
+    struct MockEcdsaRecovery {
+        genuine_digest: [u8; 32],
+        signer: String,
+    }
+
+    impl EcdsaRecovery for MockEcdsaRecovery {
+        fn recover_signer(&self, digest: [u8; 32], _v: u8, _r: [u8; 32], _s: [u8; 32]) -> Result<String, SwapError> {
+            if digest == self.genuine_digest {
+                Ok(self.signer.clone())
+            } else {
+                Ok("0x00000000000000000000000000000000000000ad".into())
+            }
+        }
+    }
+
+    fn sample_permit() -> PermitRequest {
+        PermitRequest {
+            token: "0x0000000000000000000000000000000000000001".into(),
+            owner: "0x00000000000000000000000000000000000000aa".into(),
+            spender: "0x00000000000000000000000000000000000000bb".into(),
+            value: 1_000,
+            nonce: 0,
+            deadline: 1_000,
+            v: 27,
+            r: "0x1111111111111111111111111111111111111111111111111111111111111111".into(),
+            s: "0x2222222222222222222222222222222222222222222222222222222222222222".into(),
+        }
+    }
+
+    #[test]
+    fn a_genuine_permit_signature_verifies() {
+        reset_state();
+        let permit = sample_permit();
+        let domain_separator = [7u8; 32];
+        let digest = permit_digest(domain_separator, &permit.owner, &permit.spender, permit.value, permit.nonce, permit.deadline).unwrap();
+        let recovery = MockEcdsaRecovery { genuine_digest: digest, signer: permit.owner.clone() };
+
+        let result = verify_permit_with(&permit, domain_separator, permit.nonce, 500, &recovery);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn a_tampered_permit_value_is_rejected() {
+        reset_state();
+        let permit = sample_permit();
+        let domain_separator = [7u8; 32];
+        let genuine_digest = permit_digest(domain_separator, &permit.owner, &permit.spender, permit.value, permit.nonce, permit.deadline).unwrap();
+        let recovery = MockEcdsaRecovery { genuine_digest, signer: permit.owner.clone() };
+        let mut tampered = permit.clone();
+        tampered.value = 999_999; // changes the struct hash, so recovery sees a different digest.
+
+        let result = verify_permit_with(&tampered, domain_separator, tampered.nonce, 500, &recovery);
+
+        assert_eq!(
+            result,
+            Err(SwapError::InvalidPermitSignature(
+                "recovered signer does not match the permit's claimed owner".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn a_stale_nonce_is_rejected_before_the_signature_is_even_checked() {
+        reset_state();
+        let permit = sample_permit();
+        let domain_separator = [7u8; 32];
+        let digest = permit_digest(domain_separator, &permit.owner, &permit.spender, permit.value, permit.nonce, permit.deadline).unwrap();
+        let recovery = MockEcdsaRecovery { genuine_digest: digest, signer: permit.owner.clone() };
+
+        let result = verify_permit_with(&permit, domain_separator, permit.nonce + 1, 500, &recovery);
+
+        assert!(matches!(result, Err(SwapError::InvalidPermitSignature(_))));
+    }
+
+    #[test]
+    fn the_live_recovery_stub_fails_closed_rather_than_skipping_verification() {
+        let result = LiveEcdsaRecovery.recover_signer([0u8; 32], 27, [0u8; 32], [0u8; 32]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fetching_the_domain_separator_and_nonce_drives_a_genuine_permit_through_verify_permit() {
+        reset_state();
+        let permit = sample_permit();
+        let domain_separator = [7u8; 32];
+        let digest = permit_digest(domain_separator, &permit.owner, &permit.spender, permit.value, permit.nonce, permit.deadline).unwrap();
+        let recovery = MockEcdsaRecovery { genuine_digest: digest, signer: permit.owner.clone() };
+        let mut response = domain_separator.to_vec();
+        response.extend(std::iter::repeat_n(0u8, 32)); // nonces(owner) == 0, matching permit.nonce.
+        let rpc = MockVerifyingRpc { response };
+
+        let result = block_on(verify_permit_using(&permit, 500, &rpc, &recovery));
+
+        assert!(result.is_ok());
+    }
+
+    struct MockVerifyingRpc {
+        response: Vec<u8>,
+    }
+
+    impl EvmRpc for MockVerifyingRpc {
+        fn eth_call(&self, _to: &str, data: &[u8], _commitment: Commitment) -> Result<Vec<u8>, ChainCallError> {
+            // DOMAIN_SEPARATOR() takes no extra args beyond the 4-byte selector;
+            // nonces(address) appends one 32-byte word. Route by call shape so
+            // one mock can stand in for both of `verify_permit`'s two eth_calls.
+            if data.len() > 4 {
+                Ok(self.response[32..64].to_vec())
+            } else {
+                Ok(self.response[..32].to_vec())
+            }
+        }
+    }
+
+    #[test]
+    fn unsigned_tx_encoding_matches_an_independently_built_reference_with_zero_fields() {
+        let rlp = encode_unsigned_eip1559_tx(
+            1,
+            0,
+            0,
+            0,
+            0,
+            "0x0000000000000000000000000000000000000000",
+            0,
+            &[],
+        )
+        .unwrap();
+
+        let mut expected = vec![0x02, 0xdd, 0x01, 0x80, 0x80, 0x80, 0x80, 0x94];
+        expected.extend_from_slice(&[0u8; 20]);
+        expected.extend_from_slice(&[0x80, 0x80, 0xc0]);
+
+        assert_eq!(rlp, expected);
+    }
+
+    #[test]
+    fn decodes_a_block_number_response() {
+        let raw = r#"{"jsonrpc":"2.0","id":1,"result":"0x1b4"}"#;
+        let result = parse_json_rpc_result(raw, "eth_blockNumber").unwrap();
+        assert_eq!(hex_to_u64(&result).unwrap(), 0x1b4);
+    }
+
+    #[test]
+    fn decodes_a_balance_response_wider_than_u64_as_a_decimal_string() {
+        // 2^65, which doesn't fit in a u64.
+        let raw = r#"{"jsonrpc":"2.0","id":1,"result":"0x20000000000000000"}"#;
+        let result = parse_json_rpc_result(raw, "eth_getBalance").unwrap();
+        assert_eq!(hex_to_decimal_string(&result).unwrap(), "36893488147419103232");
+    }
+
+    #[test]
+    fn decodes_a_transaction_receipt_response() {
+        let raw = r#"{"jsonrpc":"2.0","id":1,"result":{"transactionHash":"0xabc123","blockNumber":"0x5","gasUsed":"0x5208","status":"0x1","logs":[]}}"#;
+        let result = parse_json_rpc_result(raw, "eth_getTransactionReceipt").unwrap();
+        let receipt = decode_receipt(&result).unwrap();
+        assert_eq!(
+            receipt,
+            TransactionReceipt {
+                transaction_hash: "0xabc123".into(),
+                block_number: 5,
+                gas_used: 21_000,
+                status: true,
+            }
+        );
+    }
+
+    #[test]
+    fn a_failed_receipt_status_decodes_to_false() {
+        let raw = r#"{"jsonrpc":"2.0","id":1,"result":{"transactionHash":"0xabc123","blockNumber":"0x5","gasUsed":"0x5208","status":"0x0"}}"#;
+        let result = parse_json_rpc_result(raw, "eth_getTransactionReceipt").unwrap();
+        assert!(!decode_receipt(&result).unwrap().status);
+    }
+
+    #[test]
+    fn an_error_envelope_surfaces_as_a_typed_chain_call_error() {
+        let raw = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32005,"message":"rate limit exceeded"}}"#;
+        let result = parse_json_rpc_result(raw, "eth_blockNumber");
+        match result {
+            Err(SwapError::ChainCallFailed(err)) => {
+                assert_eq!(err.code, Some(-32005));
+                assert_eq!(err.message, "rate limit exceeded");
+                assert_eq!(err.raw, Some(raw.to_string()));
+            }
+            other => panic!("expected ChainCallFailed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn a_receipt_missing_a_required_field_is_reported_rather_than_panicking() {
+        let raw = r#"{"jsonrpc":"2.0","id":1,"result":{"transactionHash":"0xabc123"}}"#;
+        let result = parse_json_rpc_result(raw, "eth_getTransactionReceipt").unwrap();
+        assert!(decode_receipt(&result).is_err());
+    }
+
+    #[test]
+    fn unsigned_tx_encoding_matches_an_independently_built_reference_with_populated_fields() {
+        let rlp = encode_unsigned_eip1559_tx(
+            1,
+            9,
+            0,
+            0,
+            21_000,
+            "0x1111111111111111111111111111111111111111",
+            1,
+            b"ab",
+        )
+        .unwrap();
+
+        let mut expected = vec![0x02, 0xe1, 0x01, 0x09, 0x80, 0x80, 0x82, 0x52, 0x08, 0x94];
+        expected.extend_from_slice(&[0x11u8; 20]);
+        expected.extend_from_slice(&[0x01, 0x82, 0x61, 0x62, 0xc0]);
+
+        assert_eq!(rlp, expected);
+    }
+
+    #[test]
+    fn an_odd_length_to_address_is_rejected_instead_of_indexing_out_of_bounds() {
+        let result = encode_unsigned_eip1559_tx(1, 0, 0, 0, 0, "0xabc", 0, &[]);
+        assert!(matches!(result, Err(SwapError::InvalidHexInput(_))));
+    }
+
+    #[test]
+    fn a_non_hex_to_address_is_rejected() {
+        let result = encode_unsigned_eip1559_tx(1, 0, 0, 0, 0, "0xzz", 0, &[]);
+        assert!(matches!(result, Err(SwapError::InvalidHexInput(_))));
+    }
+
+    #[test]
+    fn erc20_allowance_covering_the_required_amount_is_accepted() {
+        assert!(require_sufficient_erc20_allowance(5_000, 5_000, "0xTOKEN").is_ok());
+    }
+
+    #[test]
+    fn erc20_allowance_shortfall_is_reported_as_a_structured_error() {
+        let result = require_sufficient_erc20_allowance(1_000, 5_000, "0xTOKEN");
+
+        assert_eq!(
+            result,
+            Err(SwapError::InsufficientAllowance {
+                current: 1_000,
+                required: 5_000,
+                token: "0xTOKEN".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn approve_call_data_uses_the_standard_erc20_selector_and_abi_encoding() {
+        let data = encode_erc20_approve_call("0x1111111111111111111111111111111111111111", 1).unwrap();
+
+        // keccak256("approve(address,uint256)")[..4], the well-known ERC-20 approve selector.
+        assert_eq!(&data[0..4], &[0x09, 0x5e, 0xa7, 0xb3]);
+        assert_eq!(data.len(), 4 + 32 + 32);
+        assert_eq!(&data[4..16], &[0u8; 12]);
+        assert_eq!(&data[16..36], &[0x11u8; 20]);
+        assert_eq!(&data[36..68], &{
+            let mut word = [0u8; 32];
+            word[31] = 1;
+            word
+        });
+    }
+
+    #[test]
+    fn a_token_not_flagged_gets_a_single_approve_call() {
+        reset_state();
+        let calls = build_erc20_approve_calls(
+            "0xfrom".into(),
+            "0xTOKEN".into(),
+            "0x1111111111111111111111111111111111111111".into(),
+            1_000,
+        )
+        .unwrap();
+        assert_eq!(calls.len(), 1);
+    }
+
+    #[test]
+    fn a_token_flagged_as_requiring_reset_gets_a_zero_approve_before_the_real_one() {
+        reset_state();
+        STATE.with(|s| {
+            s.borrow_mut()
+                .erc20_requires_approval_reset
+                .insert("0xtoken".into(), true);
+        });
+
+        let calls = build_erc20_approve_calls(
+            "0xfrom".into(),
+            "0xTOKEN".into(),
+            "0x1111111111111111111111111111111111111111".into(),
+            1_000,
+        )
+        .unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].rlp != calls[1].rlp);
+        // The zero-approve leg encodes a zero amount word, the real approve's doesn't.
+        assert!(calls[0].rlp.windows(32).any(|w| w == [0u8; 32]));
+    }
+
+    #[test]
+    fn a_malformed_spender_address_is_rejected_instead_of_trapping() {
+        reset_state();
+        let result = build_erc20_approve_calls("0xfrom".into(), "0xTOKEN".into(), "0xnot-hex".into(), 1_000);
+        assert!(matches!(result, Err(SwapError::InvalidHexInput(_))));
+    }
+
+    fn successful_receipt(tx_hash: &str) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_hash: tx_hash.to_string(),
+            block_number: 100,
+            gas_used: 21_000,
+            status: true,
+        }
+    }
+
+    struct ScriptedReceiptSource {
+        /// Each call to `poll` consumes the next entry; once exhausted, every
+        /// further poll also reports "not yet mined" so a buggy test can't
+        /// accidentally loop forever on a default.
+        responses: std::cell::RefCell<std::collections::VecDeque<Result<Option<TransactionReceipt>, SwapError>>>,
+    }
+
+    impl ScriptedReceiptSource {
+        fn new(responses: Vec<Result<Option<TransactionReceipt>, SwapError>>) -> Self {
+            Self {
+                responses: std::cell::RefCell::new(responses.into_iter().collect()),
+            }
+        }
+    }
+
+    impl ReceiptSource for ScriptedReceiptSource {
+        fn poll(&self, _tx_hash: &str) -> Result<Option<TransactionReceipt>, SwapError> {
+            self.responses.borrow_mut().pop_front().unwrap_or(Ok(None))
+        }
+    }
+
+    #[test]
+    fn a_receipt_that_shows_up_successful_on_a_later_attempt_resolves_ok() {
+        reset_state();
+        let source = ScriptedReceiptSource::new(vec![
+            Ok(None),
+            Ok(None),
+            Ok(Some(successful_receipt("0xgood"))),
+        ]);
+
+        let result = block_on(wait_for_receipt_success_using("0xgood", &source));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn a_receipt_that_shows_up_reverted_fails_immediately_without_exhausting_retries() {
+        reset_state();
+        let mut reverted = successful_receipt("0xbad");
+        reverted.status = false;
+        let source = ScriptedReceiptSource::new(vec![Ok(Some(reverted))]);
+
+        let result = block_on(wait_for_receipt_success_using("0xbad", &source));
+
+        assert_eq!(result, Err(SwapError::TransactionReverted { tx_hash: "0xbad".into() }));
+    }
+
+    #[test]
+    fn a_receipt_that_never_shows_up_times_out_after_the_configured_attempts() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().receipt_poll_policy.max_attempts = 3);
+        let source = ScriptedReceiptSource::new(vec![Ok(None), Ok(None), Ok(None)]);
+
+        let result = block_on(wait_for_receipt_success_using("0xpending", &source));
+
+        assert_eq!(
+            result,
+            Err(SwapError::ReceiptPollTimedOut { tx_hash: "0xpending".into(), attempts: 3 })
+        );
+    }
+
+    #[test]
+    fn setting_an_invalid_receipt_poll_policy_is_rejected() {
+        reset_state();
+        let result = set_receipt_poll_policy(ReceiptPollPolicy { max_attempts: 0, interval_ms: 1_000 });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ChainCallError, SwapError};
+use crate::state::STATE;
+
+/// Cycles budget for a single HTTP outcall: a flat base cost plus a
+/// per-byte charge on the expected response size, so a call that expects a
+/// large response (e.g. a paginated `eth_getLogs`) isn't short-changed by a
+/// one-size-fits-all budget while a call expecting a tiny response doesn't
+/// attach more cycles than it will ever use.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutcallCyclesPolicy {
+    pub base_cycles: u128,
+    pub cycles_per_response_byte: u128,
+}
+
+impl Default for OutcallCyclesPolicy {
+    fn default() -> Self {
+        Self {
+            base_cycles: 1_000_000_000,
+            cycles_per_response_byte: 100_000,
+        }
+    }
+}
+
+/// Admin-only: sets the cycles budget applied to outcalls of `method_class`
+/// (e.g. `"eth_call"`, `"solana_rpc"`), overriding the default policy for
+/// just that class so it can be tuned independently as fee schedules diverge.
+#[ic_cdk::update]
+pub fn set_outcall_cycles_policy(method_class: String, policy: OutcallCyclesPolicy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    set_outcall_cycles_policy_internal(method_class, policy);
+    Ok(())
+}
+
+fn set_outcall_cycles_policy_internal(method_class: String, policy: OutcallCyclesPolicy) {
+    STATE.with(|s| {
+        s.borrow_mut()
+            .outcall_cycles_by_method
+            .insert(method_class, policy);
+    });
+}
+
+/// The cycles budget for an outcall of `method_class` expecting a response
+/// of roughly `expected_response_bytes`, so a call attaches neither too few
+/// cycles (and fails outright) nor needlessly many (and wastes them). Falls
+/// back to `default_outcall_cycles_policy` for a method class with no override.
+pub fn compute_outcall_cycles(method_class: &str, expected_response_bytes: u64) -> u128 {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let policy = state
+            .outcall_cycles_by_method
+            .get(method_class)
+            .copied()
+            .unwrap_or(state.default_outcall_cycles_policy);
+        policy.base_cycles + policy.cycles_per_response_byte * expected_response_bytes as u128
+    })
+}
+
+/// Admin-only: adjust the maximum HTTP outcall response size accepted from
+/// EVM/Solana RPC endpoints.
+#[ic_cdk::update]
+pub fn set_max_rpc_response_bytes(max_bytes: u64) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if max_bytes == 0 {
+        return Err(SwapError::InvalidAmount(
+            "max_rpc_response_bytes must be positive".into(),
+        ));
+    }
+    STATE.with(|s| s.borrow_mut().config.max_rpc_response_bytes = max_bytes);
+    Ok(())
+}
+
+/// Rejects an RPC response before it's buffered/parsed if it exceeds the
+/// configured maximum, guarding against oversized outcalls.
+pub fn guard_response_size(body_len: usize) -> Result<(), SwapError> {
+    let max = STATE.with(|s| s.borrow().config.max_rpc_response_bytes);
+    if body_len as u64 > max {
+        return Err(SwapError::InvalidAmount(format!(
+            "RPC response of {body_len} bytes exceeds the configured limit of {max} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// RAII guard reserving one of the canister's allowed in-flight outcall
+/// slots. Dropping it (including via `?` early-return) releases the slot.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OutcallSlot;
+
+impl Drop for OutcallSlot {
+    fn drop(&mut self) {
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.in_flight_outcalls = state.in_flight_outcalls.saturating_sub(1);
+        });
+    }
+}
+
+/// Reserves an in-flight outcall slot, failing fast if the canister is
+/// already at its configured concurrency limit rather than risking the IC's
+/// hard per-canister outcall cap.
+pub fn acquire_outcall_slot() -> Result<OutcallSlot, SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let limit = state.config.max_in_flight_outcalls;
+        if state.in_flight_outcalls >= limit {
+            return Err(SwapError::TooManyInFlightOutcalls { limit });
+        }
+        state.in_flight_outcalls += 1;
+        Ok(OutcallSlot)
+    })
+}
+
+/// The single endpoint every EVM JSON-RPC call used before endpoints became
+/// configurable. Kept as the fallback when `State::evm_rpc_endpoints` is
+/// empty, so an un-configured canister behaves exactly as it always did.
+const DEFAULT_EVM_RPC_ENDPOINT: &str = "https://sepolia.drpc.org";
+
+/// The ordered list of EVM RPC endpoints `call_with_failover` will actually
+/// try: the configured list if one was set, otherwise just
+/// `DEFAULT_EVM_RPC_ENDPOINT`.
+pub fn effective_evm_rpc_endpoints() -> Vec<String> {
+    STATE.with(|s| {
+        let endpoints = &s.borrow().evm_rpc_endpoints;
+        if endpoints.is_empty() {
+            vec![DEFAULT_EVM_RPC_ENDPOINT.to_string()]
+        } else {
+            endpoints.clone()
+        }
+    })
+}
+
+#[ic_cdk::query]
+pub fn get_evm_rpc_endpoints() -> Vec<String> {
+    effective_evm_rpc_endpoints()
+}
+
+/// Admin-only: replaces the ordered list of EVM RPC endpoints
+/// `call_with_failover` tries in sequence. Duplicate URLs are dropped,
+/// keeping the first occurrence's position, so a caller that accidentally
+/// repeats an endpoint doesn't get it retried twice in the same failover pass.
+#[ic_cdk::update]
+pub fn set_evm_rpc_endpoints(urls: Vec<String>) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if urls.is_empty() {
+        return Err(SwapError::InvalidAmount("at least one RPC endpoint is required".into()));
+    }
+    let mut deduped = Vec::with_capacity(urls.len());
+    for url in urls {
+        if !deduped.contains(&url) {
+            deduped.push(url);
+        }
+    }
+    STATE.with(|s| s.borrow_mut().evm_rpc_endpoints = deduped);
+    Ok(())
+}
+
+/// A single attempt at sending a JSON-RPC request body to `url`. Behind a
+/// trait so `call_with_failover` can be unit tested against mocked
+/// transport/HTTP failures without a live outcall.
+pub trait RpcTransport {
+    fn send(&self, url: &str, body: &[u8]) -> Result<Vec<u8>, ChainCallError>;
+}
+
+struct LiveRpcTransport;
+
+impl RpcTransport for LiveRpcTransport {
+    fn send(&self, _url: &str, _body: &[u8]) -> Result<Vec<u8>, ChainCallError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Tries each configured EVM RPC endpoint in order, returning the first
+/// success. An endpoint whose `RpcTransport::send` errors (transport failure
+/// or non-200, it's all the same to the caller) is skipped in favor of the
+/// next one. If every endpoint fails, the error carries every individual
+/// failure rather than just the last, so a transient blip on endpoint #1
+/// doesn't hide a config mistake on endpoint #2.
+pub fn call_with_failover(body: &[u8], transport: &impl RpcTransport) -> Result<Vec<u8>, SwapError> {
+    let endpoints = effective_evm_rpc_endpoints();
+    let mut errors = Vec::with_capacity(endpoints.len());
+    for url in &endpoints {
+        match transport.send(url, body) {
+            Ok(response) => return Ok(response),
+            Err(err) => errors.push(err),
+        }
+    }
+    Err(SwapError::AllRpcEndpointsFailed(errors))
+}
+
+/// Performs a JSON-RPC call against the canister's configured EVM endpoints,
+/// failing over to the next one on a transport or non-200 error.
+pub async fn make_evm_rpc_call(body: &[u8]) -> Result<Vec<u8>, SwapError> {
+    call_with_failover(body, &LiveRpcTransport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Chain;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        // `set_evm_rpc_endpoints` is controller-gated; the test-harness
+        // caller is always the anonymous principal, so seed it as a
+        // controller here rather than in every call site below.
+        STATE.with(|s| s.borrow_mut().controllers.insert(candid::Principal::anonymous()));
+    }
+
+    #[test]
+    fn slot_is_released_when_dropped() {
+        reset_state();
+        {
+            let _slot = acquire_outcall_slot().unwrap();
+            assert_eq!(STATE.with(|s| s.borrow().in_flight_outcalls), 1);
+        }
+        assert_eq!(STATE.with(|s| s.borrow().in_flight_outcalls), 0);
+    }
+
+    #[test]
+    fn acquiring_past_the_limit_fails() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.max_in_flight_outcalls = 1);
+        let _slot = acquire_outcall_slot().unwrap();
+        assert_eq!(
+            acquire_outcall_slot(),
+            Err(SwapError::TooManyInFlightOutcalls { limit: 1 })
+        );
+    }
+
+    #[test]
+    fn response_under_limit_is_allowed() {
+        reset_state();
+        assert!(guard_response_size(1024).is_ok());
+    }
+
+    #[test]
+    fn response_over_limit_is_rejected() {
+        reset_state();
+        let max = STATE.with(|s| s.borrow().config.max_rpc_response_bytes);
+        assert!(guard_response_size(max as usize + 1).is_err());
+    }
+
+    #[test]
+    fn an_unconfigured_method_class_falls_back_to_the_default_policy() {
+        reset_state();
+        let default_policy = STATE.with(|s| s.borrow().default_outcall_cycles_policy);
+        let expected = default_policy.base_cycles + default_policy.cycles_per_response_byte * 1_000;
+        assert_eq!(compute_outcall_cycles("eth_call", 1_000), expected);
+    }
+
+    #[test]
+    fn setting_a_policy_for_one_method_class_applies_only_to_that_class() {
+        reset_state();
+        set_outcall_cycles_policy_internal(
+            "solana_rpc".into(),
+            OutcallCyclesPolicy {
+                base_cycles: 5_000_000_000,
+                cycles_per_response_byte: 1_000_000,
+            },
+        );
+
+        assert_eq!(compute_outcall_cycles("solana_rpc", 0), 5_000_000_000);
+        let default_policy = STATE.with(|s| s.borrow().default_outcall_cycles_policy);
+        assert_eq!(compute_outcall_cycles("eth_call", 0), default_policy.base_cycles);
+    }
+
+    #[test]
+    fn a_larger_expected_response_uses_a_larger_cycles_budget() {
+        reset_state();
+        let small = compute_outcall_cycles("eth_call", 1_000);
+        let large = compute_outcall_cycles("eth_call", 1_000_000);
+        assert!(large > small);
+    }
+
+    #[test]
+    fn with_no_configured_endpoints_the_built_in_default_is_used() {
+        reset_state();
+        assert_eq!(effective_evm_rpc_endpoints(), vec![DEFAULT_EVM_RPC_ENDPOINT.to_string()]);
+    }
+
+    #[test]
+    fn setting_endpoints_drops_duplicates_but_keeps_first_occurrence_order() {
+        reset_state();
+        set_evm_rpc_endpoints(vec!["a".into(), "b".into(), "a".into(), "c".into()]).unwrap();
+        assert_eq!(get_evm_rpc_endpoints(), vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn setting_an_empty_endpoint_list_is_rejected() {
+        reset_state();
+        assert!(set_evm_rpc_endpoints(vec![]).is_err());
+    }
+
+    fn transport_error(message: &str) -> ChainCallError {
+        ChainCallError {
+            chain: Chain::Ethereum,
+            method: "eth_call".into(),
+            code: None,
+            message: message.into(),
+            raw: None,
+        }
+    }
+
+    struct FailoverTransport {
+        // Each endpoint's outcome, in the order endpoints are tried.
+        outcomes: Vec<Result<Vec<u8>, ChainCallError>>,
+    }
+
+    impl RpcTransport for FailoverTransport {
+        fn send(&self, url: &str, _body: &[u8]) -> Result<Vec<u8>, ChainCallError> {
+            let index: usize = url.trim_start_matches("endpoint-").parse().unwrap();
+            self.outcomes[index].clone()
+        }
+    }
+
+    #[test]
+    fn the_first_two_endpoints_failing_falls_through_to_the_third() {
+        reset_state();
+        set_evm_rpc_endpoints(vec!["endpoint-0".into(), "endpoint-1".into(), "endpoint-2".into()]).unwrap();
+        let transport = FailoverTransport {
+            outcomes: vec![
+                Err(transport_error("rate limited")),
+                Err(transport_error("502 bad gateway")),
+                Ok(b"result".to_vec()),
+            ],
+        };
+
+        let result = call_with_failover(b"request", &transport);
+
+        assert_eq!(result, Ok(b"result".to_vec()));
+    }
+
+    #[test]
+    fn every_endpoint_failing_surfaces_every_individual_failure() {
+        reset_state();
+        set_evm_rpc_endpoints(vec!["endpoint-0".into(), "endpoint-1".into()]).unwrap();
+        let transport = FailoverTransport {
+            outcomes: vec![
+                Err(transport_error("rate limited")),
+                Err(transport_error("502 bad gateway")),
+            ],
+        };
+
+        let result = call_with_failover(b"request", &transport);
+
+        match result {
+            Err(SwapError::AllRpcEndpointsFailed(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert_eq!(errors[0].message, "rate limited");
+                assert_eq!(errors[1].message, "502 bad gateway");
+            }
+            other => panic!("expected AllRpcEndpointsFailed, got {other:?}"),
+        }
+    }
+}
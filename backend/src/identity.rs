@@ -0,0 +1,249 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+/// Key name used when `State::key_name` hasn't been set, e.g. on a fresh
+/// `dfx deploy` without an explicit `InitArg`. Matches the dfx local replica's
+/// well-known test key so local development works without extra config.
+const DEFAULT_KEY_NAME: &str = "dfx_test_key";
+
+/// The threshold signing key name both `evm.rs` and `solana.rs` derive their
+/// addresses from. Centralizing this here means the two subsystems can never
+/// drift apart the way hardcoded per-module constants would let them.
+pub fn configured_key_name() -> String {
+    STATE.with(|s| {
+        let key_name = s.borrow().key_name.clone();
+        if key_name.is_empty() { DEFAULT_KEY_NAME.to_string() } else { key_name }
+    })
+}
+
+/// Admin-only: changes the threshold signing key name used by both EVM and
+/// Solana address derivation. Takes effect on the next
+/// `refresh_canister_identity` call.
+#[ic_cdk::update]
+pub fn set_threshold_key_name(key_name: String) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if key_name.is_empty() {
+        return Err(SwapError::InvalidAmount("key name must not be empty".into()));
+    }
+    STATE.with(|s| s.borrow_mut().key_name = key_name);
+    Ok(())
+}
+
+/// Deterministic stand-in for a real threshold-signing public key
+/// derivation: the same key name always derives the same address, and a
+/// different key name always derives a different one, so rotating the key
+/// visibly changes the cached address without needing a live IC subnet.
+fn simple_hash(s: &str) -> u128 {
+    s.bytes().fold(0u128, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u128))
+}
+
+fn derive_evm_address(key_name: &str) -> String {
+    format!("0x{:040x}", simple_hash(key_name))
+}
+
+pub(crate) fn derive_solana_address(key_name: &str) -> String {
+    format!("sol{:022x}", simple_hash(key_name))
+}
+
+/// The canister's cached, re-derivable signing addresses.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterIdentity {
+    pub evm_address: String,
+    pub solana_address: String,
+}
+
+/// Admin-only: set what the on-chain escrow contract currently has
+/// configured as its trusted EVM signer, so `refresh_canister_identity` can
+/// report whether it's gone stale after a key rotation. Simulated via admin
+/// config since this canister doesn't read arbitrary contract storage.
+#[ic_cdk::update]
+pub fn set_onchain_configured_evm_signer(address: String) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().onchain_configured_evm_signer = Some(address));
+    Ok(())
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IdentityRefreshResult {
+    pub identity: CanisterIdentity,
+    /// Whether the on-chain contract's configured signer no longer matches
+    /// the freshly re-derived EVM address.
+    pub onchain_signer_mismatch: bool,
+}
+
+fn refresh_canister_identity_internal() -> IdentityRefreshResult {
+    let identity = CanisterIdentity {
+        evm_address: derive_evm_address(&crate::evm::get_canister_key_name()),
+        solana_address: derive_solana_address(&crate::solana::get_canister_ecdsa_key()),
+    };
+    let onchain_signer_mismatch = STATE.with(|s| {
+        match &s.borrow().onchain_configured_evm_signer {
+            Some(configured) => configured != &identity.evm_address,
+            None => false,
+        }
+    });
+    STATE.with(|s| s.borrow_mut().canister_identity = Some(identity.clone()));
+    IdentityRefreshResult { identity, onchain_signer_mismatch }
+}
+
+/// Admin-only: re-derives and re-caches the canister's EVM/Solana addresses
+/// from its current signing keys, e.g. after a key rotation, and reports
+/// whether the on-chain contract's configured signer has gone stale.
+#[ic_cdk::update]
+pub fn refresh_canister_identity() -> Result<IdentityRefreshResult, SwapError> {
+    crate::admin::require_admin()?;
+    Ok(refresh_canister_identity_internal())
+}
+
+/// The last cached identity from a previous `refresh_canister_identity`
+/// call, if any.
+#[ic_cdk::query]
+pub fn get_cached_canister_identity() -> Option<CanisterIdentity> {
+    STATE.with(|s| s.borrow().canister_identity.clone())
+}
+
+/// Parses caller-supplied principal text without trapping on malformed
+/// input. Every public endpoint across this canister that accepts a
+/// principal as text (rather than relying on Candid's native `Principal`
+/// type) should route through this instead of calling
+/// `Principal::from_text(...).unwrap()` itself, so a malformed value always
+/// surfaces as the same typed error.
+pub fn parse_principal_text(text: &str) -> Result<Principal, SwapError> {
+    Principal::from_text(text).map_err(|err| SwapError::InvalidPrincipal(format!("{text}: {err}")))
+}
+
+/// Derives a per-caller Solana deposit address from the canister's signing
+/// key and the caller's principal, distinct from the canister's own address
+/// (see `derive_solana_address`). Returns a typed error instead of trapping
+/// if `principal_text` isn't a well-formed principal.
+#[ic_cdk::query]
+pub fn get_solana_account_address(principal_text: String) -> Result<String, SwapError> {
+    let principal = parse_principal_text(&principal_text)?;
+    let key_name = crate::solana::get_canister_ecdsa_key();
+    Ok(derive_solana_address(&format!("{key_name}:{principal}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn refresh_populates_the_cache() {
+        reset_state();
+        assert_eq!(get_cached_canister_identity(), None);
+
+        let result = refresh_canister_identity_internal();
+
+        assert_eq!(get_cached_canister_identity(), Some(result.identity));
+    }
+
+    #[test]
+    fn refresh_is_deterministic_for_the_same_key_names() {
+        reset_state();
+        let first = refresh_canister_identity_internal();
+        let second = refresh_canister_identity_internal();
+        assert_eq!(first.identity, second.identity);
+    }
+
+    #[test]
+    fn no_mismatch_is_reported_when_no_onchain_signer_is_configured() {
+        reset_state();
+        let result = refresh_canister_identity_internal();
+        assert!(!result.onchain_signer_mismatch);
+    }
+
+    #[test]
+    fn a_stale_onchain_signer_is_reported_as_a_mismatch() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().onchain_configured_evm_signer = Some("0xstale".into()));
+
+        let result = refresh_canister_identity_internal();
+
+        assert!(result.onchain_signer_mismatch);
+    }
+
+    #[test]
+    fn a_matching_onchain_signer_reports_no_mismatch() {
+        reset_state();
+        let identity = refresh_canister_identity_internal().identity;
+        STATE.with(|s| s.borrow_mut().onchain_configured_evm_signer = Some(identity.evm_address.clone()));
+
+        let result = refresh_canister_identity_internal();
+
+        assert!(!result.onchain_signer_mismatch);
+    }
+
+    #[test]
+    fn evm_and_solana_both_read_the_default_key_name_when_unset() {
+        reset_state();
+        assert_eq!(crate::evm::get_canister_key_name(), DEFAULT_KEY_NAME);
+        assert_eq!(crate::solana::get_canister_ecdsa_key(), DEFAULT_KEY_NAME);
+    }
+
+    #[test]
+    fn evm_and_solana_both_read_the_configured_key_name_after_it_is_set() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().key_name = "rotated_key".into());
+
+        assert_eq!(crate::evm::get_canister_key_name(), "rotated_key");
+        assert_eq!(crate::solana::get_canister_ecdsa_key(), "rotated_key");
+    }
+
+    #[test]
+    fn setting_an_empty_key_name_is_rejected() {
+        reset_state();
+        assert!(set_threshold_key_name("".into()).is_err());
+    }
+
+    #[test]
+    fn rotating_the_key_name_changes_the_derived_identity() {
+        reset_state();
+        let before = refresh_canister_identity_internal().identity;
+
+        STATE.with(|s| s.borrow_mut().key_name = "rotated_key".into());
+        let after = refresh_canister_identity_internal().identity;
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn parses_a_well_formed_principal() {
+        let principal = parse_principal_text("2vxsx-fae").unwrap();
+        assert_eq!(principal, Principal::anonymous());
+    }
+
+    #[test]
+    fn a_malformed_principal_is_a_clean_error_not_a_trap() {
+        let result = parse_principal_text("not-a-principal!!");
+        assert!(matches!(result, Err(SwapError::InvalidPrincipal(_))));
+    }
+
+    #[test]
+    fn get_solana_account_address_rejects_malformed_principal_text_cleanly() {
+        reset_state();
+        let result = get_solana_account_address("not-a-principal!!".into());
+        assert!(matches!(result, Err(SwapError::InvalidPrincipal(_))));
+    }
+
+    #[test]
+    fn get_solana_account_address_succeeds_for_a_well_formed_principal() {
+        reset_state();
+        let address = get_solana_account_address("2vxsx-fae".into()).unwrap();
+        assert!(address.starts_with("sol"));
+    }
+
+    #[test]
+    fn different_principals_get_different_solana_addresses() {
+        reset_state();
+        let anonymous = get_solana_account_address("2vxsx-fae".into()).unwrap();
+        let other = get_solana_account_address(Principal::from_slice(&[1; 29]).to_text()).unwrap();
+        assert_ne!(anonymous, other);
+    }
+}
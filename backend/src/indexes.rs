@@ -0,0 +1,102 @@
+use candid::Principal;
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+pub fn add_to_indexes(maker: Principal, order_id: &str, client_reference: Option<&str>) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state
+            .maker_index
+            .entry(maker)
+            .or_default()
+            .push(order_id.to_string());
+        if let Some(reference) = client_reference {
+            state
+                .reference_index
+                .entry(reference.to_string())
+                .or_default()
+                .push(order_id.to_string());
+        }
+    });
+}
+
+/// Rebuilds the maker and client-reference secondary indexes from scratch by
+/// scanning every order. Intended for recovery after a manual state import
+/// (e.g. restoring from a stable-memory snapshot) where the indexes weren't
+/// part of the imported data.
+#[ic_cdk::update]
+pub fn rebuild_indexes() -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    rebuild_indexes_internal();
+    Ok(())
+}
+
+pub fn rebuild_indexes_internal() {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.maker_index.clear();
+        state.reference_index.clear();
+
+        let entries: Vec<(Principal, String, Option<String>)> = state
+            .orders
+            .values()
+            .map(|o| (o.maker, o.id.clone(), o.client_reference.clone()))
+            .collect();
+
+        for (maker, order_id, client_reference) in entries {
+            state.maker_index.entry(maker).or_default().push(order_id.clone());
+            if let Some(reference) = client_reference {
+                state.reference_index.entry(reference).or_default().push(order_id);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Chain, CreateOrderRequest};
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn rebuild_recovers_indexes_after_manual_import() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = crate::orders::create_cross_chain_swap_order_internal(
+            maker,
+            CreateOrderRequest {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: Some("invoice-1".into()),
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap();
+
+        // Simulate a manual state import that populated `orders` but not the indexes.
+        STATE.with(|s| {
+            s.borrow_mut().maker_index.clear();
+            s.borrow_mut().reference_index.clear();
+        });
+
+        rebuild_indexes_internal();
+
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.maker_index[&maker], vec![order_id.clone()]);
+            assert_eq!(state.reference_index["invoice-1"], vec![order_id]);
+        });
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+/// Sizes of the bounded caches that get pruned on each heartbeat, so
+/// operators can confirm pruning is actually keeping them bounded.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheStats {
+    pub idempotency_cache_entries: u64,
+    pub reservation_cache_entries: u64,
+}
+
+#[ic_cdk::query]
+pub fn get_cache_stats() -> CacheStats {
+    STATE.with(|s| {
+        let state = s.borrow();
+        CacheStats {
+            idempotency_cache_entries: state.solana_replay_cache.len() as u64,
+            reservation_cache_entries: state.recent_cancellations.len() as u64,
+        }
+    })
+}
+
+/// Prunes the idempotency and reservation caches down to entries still
+/// within their configured TTLs. Called from the heartbeat so both caches
+/// stay bounded without an admin needing to do it manually.
+pub fn prune_caches(now: u64) {
+    let (idempotency_ttl, reservation_ttl) = STATE.with(|s| {
+        let config = &s.borrow().config;
+        (config.idempotency_cache_ttl_secs, config.reservation_cache_ttl_secs)
+    });
+    crate::solana::prune_expired_replay_cache(now, idempotency_ttl);
+    crate::orders::prune_expired_reservation_cache(now, reservation_ttl);
+}
+
+#[ic_cdk::heartbeat]
+fn heartbeat() {
+    let now = ic_cdk::api::time();
+    prune_caches(now);
+    crate::orders::sweep_stalled_swaps(now);
+    crate::orders::sweep_auto_refund_orders(now);
+}
+
+/// Runs the expiry sweep exactly once, guarded so an overlapping interval
+/// tick skips instead of refunding the same order twice. Returns whether it
+/// actually ran. Takes `now` explicitly (rather than calling
+/// `ic_cdk::api::time()` itself) so the sweep stays unit testable with an
+/// injected clock, same as `orders::sweep_expired_orders`.
+pub fn run_expiry_sweep(now: u64) -> bool {
+    let already_running = STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.expiry_sweep_in_progress {
+            return true;
+        }
+        state.expiry_sweep_in_progress = true;
+        false
+    });
+    if already_running {
+        return false;
+    }
+    crate::orders::sweep_expired_orders(now);
+    STATE.with(|s| s.borrow_mut().expiry_sweep_in_progress = false);
+    true
+}
+
+/// Registers the recurring expiry-sweep timer at the interval configured in
+/// `CanisterConfig::expiry_sweep_interval_secs`, clearing any previously
+/// registered timer first so repeated calls (from `init`, `post_upgrade`, or
+/// `set_expiry_sweep_interval`) never stack overlapping timers.
+pub fn schedule_expiry_sweep() {
+    let interval_secs = STATE.with(|s| s.borrow().config.expiry_sweep_interval_secs);
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if let Some(timer_id) = state.expiry_sweep_timer.take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+        state.expiry_sweep_timer = Some(ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(interval_secs),
+            || {
+                run_expiry_sweep(ic_cdk::api::time());
+            },
+        ));
+    });
+}
+
+/// Admin-only: adjusts how often the timer-driven expiry sweep runs,
+/// re-registering the timer at the new interval immediately rather than
+/// waiting for the next canister upgrade.
+#[ic_cdk::update]
+pub fn set_expiry_sweep_interval(secs: u64) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if secs == 0 {
+        return Err(SwapError::InvalidAmount("sweep interval must be positive".into()));
+    }
+    STATE.with(|s| s.borrow_mut().config.expiry_sweep_interval_secs = secs);
+    schedule_expiry_sweep();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn cache_stats_reports_current_sizes() {
+        reset_state();
+        crate::solana::check_and_record_submission("blockhash1", b"tx", 0).unwrap();
+        STATE.with(|s| s.borrow_mut().recent_cancellations.insert("fingerprint".into(), 0));
+
+        let stats = get_cache_stats();
+        assert_eq!(stats.idempotency_cache_entries, 1);
+        assert_eq!(stats.reservation_cache_entries, 1);
+    }
+
+    #[test]
+    fn prune_caches_drops_stale_entries_and_keeps_fresh_ones() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.config.idempotency_cache_ttl_secs = 50;
+            state.config.reservation_cache_ttl_secs = 50;
+        });
+        crate::solana::check_and_record_submission("stale", b"tx", 0).unwrap();
+        crate::solana::check_and_record_submission("fresh", b"tx", 90).unwrap();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.recent_cancellations.insert("stale-fp".into(), 0);
+            state.recent_cancellations.insert("fresh-fp".into(), 90);
+        });
+
+        prune_caches(100);
+
+        let stats = get_cache_stats();
+        assert_eq!(stats.idempotency_cache_entries, 1);
+        assert_eq!(stats.reservation_cache_entries, 1);
+    }
+
+    fn base_request() -> crate::types::CreateOrderRequest {
+        crate::types::CreateOrderRequest {
+            src_chain: crate::types::Chain::ICP,
+            dst_chain: crate::types::Chain::Ethereum,
+            src_token: "ICP".into(),
+            dst_token: "ETH".into(),
+            amount: 10_000,
+            destination_address: "0xdead".into(),
+            escrowed_safety_deposit: 100,
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            timelocks: None,
+        }
+    }
+
+    #[test]
+    fn run_expiry_sweep_refunds_an_order_past_its_timelock_at_the_injected_time() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let order_id = crate::orders::create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| s.borrow_mut().config.refund_grace_secs = 0);
+        let expires_at = STATE.with(|s| s.borrow().orders[&order_id].expires_at);
+
+        let ran = run_expiry_sweep(expires_at);
+
+        assert!(ran);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, crate::types::SwapOrderStatus::Refunded);
+        });
+    }
+
+    #[test]
+    fn run_expiry_sweep_skips_while_a_sweep_is_already_in_progress() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let order_id = crate::orders::create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| s.borrow_mut().config.refund_grace_secs = 0);
+        let expires_at = STATE.with(|s| s.borrow().orders[&order_id].expires_at);
+        STATE.with(|s| s.borrow_mut().expiry_sweep_in_progress = true);
+
+        let ran = run_expiry_sweep(expires_at);
+
+        assert!(!ran);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, crate::types::SwapOrderStatus::Created);
+        });
+    }
+}
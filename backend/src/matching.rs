@@ -0,0 +1,553 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+use crate::types::{Chain, CreateOrderRequest, PairingKey, SwapOrder, SwapOrderStatus};
+
+/// How much of `order.amount` is still available to match against a
+/// counter-order, after accounting for any partial fills it's already taken.
+fn remaining_amount(order: &SwapOrder) -> u128 {
+    order.amount.saturating_sub(order.filled_amount)
+}
+
+/// Whether `src_chain`/`dst_chain`/`src_token`/`dst_token` describe the
+/// opposite leg of `other`, i.e. the two would settle against each other:
+/// what one side sends is what the other side wants to receive, and vice
+/// versa.
+fn is_opposite_leg(src_chain: Chain, dst_chain: Chain, src_token: &str, dst_token: &str, other: &SwapOrder) -> bool {
+    src_chain == other.dst_chain
+        && dst_chain == other.src_chain
+        && src_token == other.dst_token
+        && dst_token == other.src_token
+}
+
+/// Whether two existing orders could settle against each other, comparing
+/// remaining (`amount - filled_amount`, saturating) rather than original
+/// amounts, so a partially-filled order only needs a counter-order sized to
+/// its residual. The subtraction saturates instead of underflowing/panicking
+/// if `filled_amount` were ever to exceed `amount`.
+pub fn is_compatible_orders(a: &SwapOrder, b: &SwapOrder) -> bool {
+    is_opposite_leg(a.src_chain, a.dst_chain, &a.src_token, &a.dst_token, b)
+        && remaining_amount(a) > 0
+        && remaining_amount(b) > 0
+}
+
+/// The bucket key an order is filed under in `State::pairing_index`: its own
+/// leg, not the complementary leg it would pair against.
+pub fn pairing_index_key(order: &SwapOrder) -> PairingKey {
+    (order.src_chain, order.src_token.clone(), order.dst_chain, order.dst_token.clone())
+}
+
+/// Files a newly-created order into `pairing_index` so future scans can find
+/// it by its complementary bucket instead of walking every order.
+pub fn index_for_pairing(state: &mut crate::state::State, order: &SwapOrder) {
+    state
+        .pairing_index
+        .entry(pairing_index_key(order))
+        .or_default()
+        .insert((order.created_at, order.id.clone()));
+}
+
+/// Removes an order from `pairing_index` once it's no longer `Created`
+/// (paired or cancelled), so a scan's per-call cap isn't eaten by orders that
+/// can't match anymore.
+pub fn deindex_from_pairing(state: &mut crate::state::State, order: &SwapOrder) {
+    if let Some(bucket) = state.pairing_index.get_mut(&pairing_index_key(order)) {
+        bucket.remove(&(order.created_at, order.id.clone()));
+    }
+}
+
+/// Upper bound on how many indexed candidates a single `preview_pairing` call
+/// will examine. Any remainder is left in the index for a later call (e.g.
+/// the heartbeat sweep) rather than examined here, so creation cost stays
+/// bounded regardless of how deep the book is on one side.
+pub const MAX_PAIRING_SCAN_CANDIDATES: usize = 100;
+
+/// One existing order a prospective order would pair against, and how much
+/// of each side that particular match would actually fill.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PairingCandidate {
+    pub order_id: String,
+    pub fill_amount: u128,
+}
+
+/// Read-only preview of how a prospective order would pair against the
+/// current open book, without creating or mutating anything.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PairingPreview {
+    pub candidates: Vec<PairingCandidate>,
+    pub total_fillable: u128,
+    pub remaining_unfilled: u128,
+    /// Whether the complementary bucket held more candidates than
+    /// `MAX_PAIRING_SCAN_CANDIDATES` allowed this call to examine. The
+    /// unexamined remainder is still in the index and will be picked up by a
+    /// later scan.
+    pub truncated: bool,
+}
+
+/// Shared by `preview_pairing` (read-only) and `fill_order_internal`
+/// (mutating): walks the bucket of open orders sharing the complementary
+/// leg of `key`, oldest first, capped at `MAX_PAIRING_SCAN_CANDIDATES`,
+/// greedily filling `amount` against however much of it each candidate
+/// still has remaining. Does not touch state itself.
+fn scan_bucket(key: &PairingKey, amount: u128) -> PairingPreview {
+    let mut candidates = Vec::new();
+    let mut total_fillable: u128 = 0;
+    let mut remaining_needed = amount;
+    let mut truncated = false;
+
+    STATE.with(|s| {
+        let state = s.borrow();
+        let Some(bucket) = state.pairing_index.get(key) else {
+            return;
+        };
+
+        for (examined, (_, order_id)) in bucket.iter().enumerate() {
+            if remaining_needed == 0 {
+                break;
+            }
+            if examined >= MAX_PAIRING_SCAN_CANDIDATES {
+                truncated = true;
+                break;
+            }
+
+            let Some(order) = state.orders.get(order_id) else {
+                continue;
+            };
+            if order.status != SwapOrderStatus::Created {
+                continue;
+            }
+            let fill = remaining_amount(order).min(remaining_needed);
+            if fill == 0 {
+                continue;
+            }
+            candidates.push(PairingCandidate {
+                order_id: order.id.clone(),
+                fill_amount: fill,
+            });
+            total_fillable += fill;
+            remaining_needed -= fill;
+        }
+    });
+
+    PairingPreview {
+        candidates,
+        total_fillable,
+        remaining_unfilled: remaining_needed,
+        truncated,
+    }
+}
+
+/// Runs the same compatibility/fill logic pairing would use, read-only,
+/// against the bucket of open orders sharing the complementary token pair,
+/// oldest first, capped at `MAX_PAIRING_SCAN_CANDIDATES`. Does not touch state.
+pub fn preview_pairing(request: &CreateOrderRequest) -> PairingPreview {
+    let key = (request.dst_chain, request.dst_token.clone(), request.src_chain, request.src_token.clone());
+    scan_bucket(&key, request.amount)
+}
+
+#[ic_cdk::query]
+pub fn get_pairing_preview(request: CreateOrderRequest) -> PairingPreview {
+    preview_pairing(&request)
+}
+
+/// Matches an open order (`order_id`, still `Created`) against the book,
+/// applying exactly what `preview_pairing` would have previewed for it
+/// instead of just reporting it. Each candidate fills `min(order.remaining,
+/// counter.remaining)`, incrementing `filled_amount` on both sides; a side
+/// whose remaining reaches zero is promoted to `Paired` and deindexed, while
+/// a side left with a residual stays `Created` so a later order can still
+/// match it. Every fill segment gets its own HTLC, locked behind a hashlock
+/// derived from a fresh per-segment secret (see
+/// `secrets::derive_fill_secret_internal`), so revealing the secret that
+/// claims one segment can't be used to claim any other segment of the same
+/// order.
+pub fn fill_order_internal(order_id: &str, now: u64) -> Result<Vec<PairingCandidate>, SwapError> {
+    let (key, remaining_needed, master_seed) = STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+        if order.status != SwapOrderStatus::Created {
+            return Err(SwapError::InvalidAmount("order is not open for matching".into()));
+        }
+        let key = (order.dst_chain, order.dst_token.clone(), order.src_chain, order.src_token.clone());
+        Ok::<_, SwapError>((key, remaining_amount(order), state.master_seed.clone()))
+    })?;
+    // Checked up front, before any fill is applied, so a missing master seed
+    // can't leave some candidates partially matched and others not.
+    if master_seed.is_empty() {
+        return Err(SwapError::InvalidAmount("master seed is not initialized".into()));
+    }
+
+    let candidates = scan_bucket(&key, remaining_needed).candidates;
+    for (index, candidate) in candidates.iter().enumerate() {
+        apply_fill(order_id, &candidate.order_id, candidate.fill_amount, &master_seed, now, index as u64)?;
+    }
+    Ok(candidates)
+}
+
+#[ic_cdk::update]
+pub fn fill_order(order_id: String) -> Result<Vec<PairingCandidate>, SwapError> {
+    fill_order_internal(&order_id, ic_cdk::api::time())
+}
+
+/// Applies one matched fill between `order_id` and `counter_id`: increments
+/// `filled_amount` on both sides by `amount`, promotes either side to
+/// `Paired` (and deindexes it) once its remaining amount reaches zero, and
+/// records a dedicated HTLC escrow per side for this fill segment.
+fn apply_fill(
+    order_id: &str,
+    counter_id: &str,
+    amount: u128,
+    master_seed: &[u8],
+    now: u64,
+    fill_index: u64,
+) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let coordination_timeout = state.config.coordination_timeout_secs;
+        for id in [order_id, counter_id] {
+            let Some(order) = state.orders.get_mut(id) else { continue };
+            order.filled_amount = order.filled_amount.saturating_add(amount);
+            if order.amount.saturating_sub(order.filled_amount) == 0 {
+                order.status = SwapOrderStatus::Paired;
+                order.coordination_deadline = Some(now + coordination_timeout);
+            }
+        }
+        for id in [order_id, counter_id] {
+            if let Some(order) = state.orders.get(id) {
+                if order.status == SwapOrderStatus::Paired {
+                    let paired_order = order.clone();
+                    deindex_from_pairing(&mut state, &paired_order);
+                }
+            }
+        }
+    });
+
+    for id in [order_id, counter_id] {
+        let secret = crate::secrets::derive_fill_secret_internal(master_seed, id, fill_index)?;
+        let hashlock = Keccak256::digest(&secret).to_vec();
+        crate::htlc::create_htlc_escrow(id, hashlock)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    fn request(src_chain: Chain, dst_chain: Chain, src_token: &str, dst_token: &str, amount: u128) -> CreateOrderRequest {
+        CreateOrderRequest {
+            src_chain,
+            dst_chain,
+            src_token: src_token.into(),
+            dst_token: dst_token.into(),
+            amount,
+            destination_address: "0xdead".into(),
+            escrowed_safety_deposit: 100,
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            timelocks: None,
+        }
+    }
+
+    fn create_order(req: CreateOrderRequest, now: u64) -> String {
+        crate::orders::create_cross_chain_swap_order_internal(Principal::anonymous(), req, now).unwrap()
+    }
+
+    #[test]
+    fn preview_finds_a_single_fully_matching_counter_order() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert_eq!(preview.candidates, vec![PairingCandidate { order_id: counter_id, fill_amount: 10_000 }]);
+        assert_eq!(preview.total_fillable, 10_000);
+        assert_eq!(preview.remaining_unfilled, 0);
+    }
+
+    #[test]
+    fn preview_reports_a_partial_fill_when_the_counter_order_is_smaller() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 4_000), 0);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert_eq!(preview.candidates, vec![PairingCandidate { order_id: counter_id, fill_amount: 4_000 }]);
+        assert_eq!(preview.total_fillable, 4_000);
+        assert_eq!(preview.remaining_unfilled, 6_000);
+    }
+
+    #[test]
+    fn preview_spans_multiple_counter_orders_oldest_first() {
+        reset_state();
+        let first = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 3_000), 0);
+        let second = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 3_000), 10);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 5_000));
+
+        assert_eq!(
+            preview.candidates,
+            vec![
+                PairingCandidate { order_id: first, fill_amount: 3_000 },
+                PairingCandidate { order_id: second, fill_amount: 2_000 },
+            ]
+        );
+        assert_eq!(preview.total_fillable, 5_000);
+        assert_eq!(preview.remaining_unfilled, 0);
+    }
+
+    #[test]
+    fn preview_ignores_orders_on_the_wrong_leg_or_token() {
+        reset_state();
+        create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+        create_order(request(Chain::Ethereum, Chain::ICP, "USDC", "ICP", 10_000), 0);
+        create_order(request(Chain::Solana, Chain::ICP, "ETH", "ICP", 10_000), 0);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert!(preview.candidates.is_empty());
+        assert_eq!(preview.remaining_unfilled, 10_000);
+    }
+
+    #[test]
+    fn preview_ignores_an_already_fully_filled_order() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&counter_id).unwrap().filled_amount = 10_000);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert!(preview.candidates.is_empty());
+    }
+
+    #[test]
+    fn preview_matches_exactly_what_creation_would_have_paired_with() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 7_000), 0);
+
+        let req = request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 7_000);
+        let preview = preview_pairing(&req);
+        let prospective_id = create_order(req, 1);
+
+        let counter_order = STATE.with(|s| s.borrow().orders[&counter_id].clone());
+        let prospective_order = STATE.with(|s| s.borrow().orders[&prospective_id].clone());
+        assert!(is_compatible_orders(&prospective_order, &counter_order));
+        assert_eq!(preview.candidates, vec![PairingCandidate { order_id: counter_id, fill_amount: 7_000 }]);
+    }
+
+    #[test]
+    fn is_compatible_orders_matches_a_partially_filled_order_against_a_residual_sized_counter() {
+        reset_state();
+        let partially_filled = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000), 0);
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&partially_filled).unwrap().filled_amount = 6_000);
+        // Residual on the first order is 10_000 - 6_000 = 4_000; this counter
+        // is sized to exactly that residual, not the original amount.
+        let residual_counter = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 4_000), 0);
+
+        let (order_a, order_b) = STATE.with(|s| {
+            let state = s.borrow();
+            (state.orders[&partially_filled].clone(), state.orders[&residual_counter].clone())
+        });
+        assert!(is_compatible_orders(&order_a, &order_b));
+    }
+
+    #[test]
+    fn is_compatible_orders_rejects_an_order_that_has_already_been_fully_filled() {
+        reset_state();
+        let fully_filled = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000), 0);
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&fully_filled).unwrap().filled_amount = 10_000);
+        let counter = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+
+        let (order_a, order_b) = STATE.with(|s| {
+            let state = s.borrow();
+            (state.orders[&fully_filled].clone(), state.orders[&counter].clone())
+        });
+        assert!(!is_compatible_orders(&order_a, &order_b));
+    }
+
+    #[test]
+    fn is_compatible_orders_does_not_underflow_when_filled_amount_exceeds_amount() {
+        reset_state();
+        let over_filled = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 1_000), 0);
+        // Shouldn't happen in practice, but the saturating subtraction must
+        // not panic or wrap if it ever does.
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&over_filled).unwrap().filled_amount = 5_000);
+        let counter = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 1_000), 0);
+
+        let (order_a, order_b) = STATE.with(|s| {
+            let state = s.borrow();
+            (state.orders[&over_filled].clone(), state.orders[&counter].clone())
+        });
+        assert!(!is_compatible_orders(&order_a, &order_b));
+    }
+
+    #[test]
+    fn is_compatible_orders_requires_opposite_legs() {
+        reset_state();
+        let a = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 1_000), 0);
+        let b = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 1_000), 0);
+
+        let (order_a, order_b) = STATE.with(|s| {
+            let state = s.borrow();
+            (state.orders[&a].clone(), state.orders[&b].clone())
+        });
+        assert!(!is_compatible_orders(&order_a, &order_b));
+    }
+
+    #[test]
+    fn preview_only_examines_the_complementary_bucket() {
+        reset_state();
+        // Same-leg noise that would have to be scanned under a full
+        // `orders.values()` walk, but isn't in the complementary bucket.
+        for _ in 0..5 {
+            create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 1_000), 0);
+        }
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert_eq!(preview.candidates, vec![PairingCandidate { order_id: counter_id, fill_amount: 10_000 }]);
+        assert!(!preview.truncated);
+    }
+
+    #[test]
+    fn preview_caps_examined_candidates_and_reports_truncation() {
+        reset_state();
+        for i in 0..MAX_PAIRING_SCAN_CANDIDATES + 10 {
+            create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 1), i as u64);
+        }
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", u128::MAX));
+
+        assert_eq!(preview.candidates.len(), MAX_PAIRING_SCAN_CANDIDATES);
+        assert!(preview.truncated);
+    }
+
+    #[test]
+    fn a_cancelled_order_is_removed_from_the_pairing_index() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+        crate::orders::cancel_order_internal(&counter_id, 1).unwrap();
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert!(preview.candidates.is_empty());
+    }
+
+    #[test]
+    fn a_paired_order_is_removed_from_the_pairing_index() {
+        reset_state();
+        let counter_id = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let order = state.orders.get_mut(&counter_id).unwrap();
+            order.status = SwapOrderStatus::Paired;
+            let paired_order = order.clone();
+            deindex_from_pairing(&mut state, &paired_order);
+        });
+
+        let preview = preview_pairing(&request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000));
+
+        assert!(preview.candidates.is_empty());
+    }
+
+    fn set_master_seed() {
+        STATE.with(|s| s.borrow_mut().master_seed = vec![7u8; 32]);
+    }
+
+    #[test]
+    fn a_big_order_is_fully_filled_by_two_smaller_counter_orders() {
+        reset_state();
+        set_master_seed();
+        let first = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 3_000), 0);
+        let second = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 7_000), 10);
+        let big = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000), 20);
+
+        let candidates = fill_order_internal(&big, 30).unwrap();
+
+        assert_eq!(
+            candidates,
+            vec![
+                PairingCandidate { order_id: first.clone(), fill_amount: 3_000 },
+                PairingCandidate { order_id: second.clone(), fill_amount: 7_000 },
+            ]
+        );
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&big].filled_amount, 10_000);
+            assert_eq!(state.orders[&big].status, SwapOrderStatus::Paired);
+            assert_eq!(state.orders[&first].status, SwapOrderStatus::Paired);
+            assert_eq!(state.orders[&second].status, SwapOrderStatus::Paired);
+            // One fill segment's HTLC per side per match.
+            assert_eq!(state.htlcs[&big].len(), 2);
+            assert_eq!(state.htlcs[&first].len(), 1);
+            assert_eq!(state.htlcs[&second].len(), 1);
+        });
+    }
+
+    #[test]
+    fn a_dust_remainder_leaves_the_order_open_for_further_matching() {
+        reset_state();
+        set_master_seed();
+        let counter = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 9_995), 0);
+        let big = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000), 10);
+
+        let candidates = fill_order_internal(&big, 20).unwrap();
+
+        assert_eq!(candidates, vec![PairingCandidate { order_id: counter.clone(), fill_amount: 9_995 }]);
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&big].filled_amount, 9_995);
+            // Still open: only 5 units of dust left unfilled.
+            assert_eq!(state.orders[&big].status, SwapOrderStatus::Created);
+            assert_eq!(state.orders[&counter].status, SwapOrderStatus::Paired);
+        });
+
+        let dust_counter = create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 5), 30);
+        let candidates = fill_order_internal(&big, 40).unwrap();
+
+        assert_eq!(candidates, vec![PairingCandidate { order_id: dust_counter, fill_amount: 5 }]);
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&big].filled_amount, 10_000);
+            assert_eq!(state.orders[&big].status, SwapOrderStatus::Paired);
+        });
+    }
+
+    #[test]
+    fn fill_order_requires_an_initialized_master_seed() {
+        reset_state();
+        create_order(request(Chain::Ethereum, Chain::ICP, "ETH", "ICP", 10_000), 0);
+        let big = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 10_000), 10);
+
+        let result = fill_order_internal(&big, 20);
+
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+        // No partial fill should have been applied before the check failed.
+        STATE.with(|s| assert_eq!(s.borrow().orders[&big].filled_amount, 0));
+    }
+
+    #[test]
+    fn fill_order_rejects_an_order_that_is_not_open_for_matching() {
+        reset_state();
+        set_master_seed();
+        let order_id = create_order(request(Chain::ICP, Chain::Ethereum, "ICP", "ETH", 1_000), 0);
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&order_id).unwrap().status = SwapOrderStatus::Paired);
+
+        let result = fill_order_internal(&order_id, 0);
+
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+}
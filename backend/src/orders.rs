@@ -0,0 +1,1305 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::config::required_safety_deposit;
+use crate::errors::SwapError;
+use crate::state::STATE;
+use crate::types::{Chain, CreateOrderRequest, SwapOrder, SwapOrderStatus};
+
+/// Maximum length of a caller-supplied `client_reference`, in bytes.
+pub const MAX_CLIENT_REFERENCE_LEN: usize = 128;
+
+#[ic_cdk::update]
+pub async fn create_cross_chain_swap_order(req: CreateOrderRequest) -> Result<String, SwapError> {
+    create_cross_chain_swap_order_checked(ic_cdk::caller(), ic_cdk::id(), req, ic_cdk::api::time()).await
+}
+
+/// Checks an ICP-sourced maker's ICRC-2 allowance before delegating to
+/// `create_cross_chain_swap_order_internal`, so a missing approval surfaces
+/// as a clean error instead of an order that's persisted but can never
+/// actually pull its funds. `spender` is whoever the allowance must be
+/// granted to (the canister itself in production); kept as an explicit
+/// parameter, same as `maker` and `now`, so this stays unit testable without
+/// a live ledger canister. See `icrc::get_icrc_allowance`.
+pub async fn create_cross_chain_swap_order_checked(
+    maker: Principal,
+    spender: Principal,
+    req: CreateOrderRequest,
+    now: u64,
+) -> Result<String, SwapError> {
+    if req.src_chain == Chain::ICP {
+        if let Some(ledger) = crate::tokens::icrc_ledger_canister_for(&req.src_token) {
+            let allowance = crate::icrc::get_icrc_allowance(ledger, maker, spender).await?;
+            crate::icrc::require_sufficient_allowance(allowance, req.amount, &req.src_token)?;
+        }
+    }
+    create_cross_chain_swap_order_internal(maker, req, now)
+}
+
+/// Fingerprints the parameters that make two orders "the same" for cooldown
+/// purposes: same maker, same chains/tokens/amount/destination.
+fn params_fingerprint(maker: Principal, req: &CreateOrderRequest) -> String {
+    format!(
+        "{maker}|{:?}|{:?}|{}|{}|{}|{}",
+        req.src_chain, req.dst_chain, req.src_token, req.dst_token, req.amount, req.destination_address
+    )
+}
+
+/// Pure core of order creation, kept free of `ic_cdk` calls so it can be unit tested.
+pub fn create_cross_chain_swap_order_internal(
+    maker: Principal,
+    mut req: CreateOrderRequest,
+    now: u64,
+) -> Result<String, SwapError> {
+    if req.dst_chain.is_evm() {
+        req.destination_address = crate::evm::normalize_evm_address(&req.destination_address);
+    }
+    if STATE.with(|s| s.borrow().config.draining) {
+        return Err(SwapError::Draining);
+    }
+    if req.amount == 0 {
+        return Err(SwapError::InvalidAmount("amount must be positive".into()));
+    }
+    if req.src_chain == req.dst_chain {
+        return Err(SwapError::UnsupportedChainPair {
+            src_chain: req.src_chain,
+            dst_chain: req.dst_chain,
+        });
+    }
+    crate::tokens::require_token_not_paused(&req.src_token)?;
+    crate::tokens::require_token_not_paused(&req.dst_token)?;
+    crate::tokens::require_amount_within_bounds(&req.src_token, req.amount)?;
+    if let Some(reference) = &req.client_reference {
+        if reference.len() > MAX_CLIENT_REFERENCE_LEN {
+            return Err(SwapError::InvalidClientReference(format!(
+                "client_reference exceeds {MAX_CLIENT_REFERENCE_LEN} bytes"
+            )));
+        }
+    }
+    let destinations = req
+        .destinations
+        .as_ref()
+        .map(|dests| crate::htlc::validate_split_payout(req.dst_chain, req.amount, dests))
+        .transpose()?;
+
+    let fingerprint = params_fingerprint(maker, &req);
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if let Some(&cancelled_at) = state.recent_cancellations.get(&fingerprint) {
+            let cooldown = state.config.cancel_recreate_cooldown_secs;
+            let elapsed = now.saturating_sub(cancelled_at);
+            if elapsed < cooldown {
+                return Err(SwapError::CooldownActive {
+                    remaining_secs: cooldown - elapsed,
+                });
+            }
+        }
+
+        let max_backlog = state.config.max_pending_pairing_backlog;
+        let pending = state.orders.values().filter(|o| o.status == SwapOrderStatus::Created).count();
+        if pending >= max_backlog {
+            return Err(SwapError::BacklogFull { max: max_backlog });
+        }
+
+        let required = required_safety_deposit(req.amount, state.config.safety_deposit_bps);
+        if req.escrowed_safety_deposit < required {
+            return Err(SwapError::InsufficientSafetyDeposit {
+                required,
+                provided: req.escrowed_safety_deposit,
+            });
+        }
+
+        let seq = state.next_order_seq;
+        state.next_order_seq += 1;
+        let id = format!("order-{seq}");
+        let default_order_ttl_secs = state.config.default_order_ttl_secs;
+        let default_timelocks = state.config.default_timelocks;
+
+        state.orders.insert(
+            id.clone(),
+            SwapOrder {
+                id: id.clone(),
+                maker,
+                src_chain: req.src_chain,
+                dst_chain: req.dst_chain,
+                src_token: req.src_token,
+                dst_token: req.dst_token,
+                amount: req.amount,
+                destination_address: req.destination_address,
+                safety_deposit: req.escrowed_safety_deposit,
+                status: SwapOrderStatus::Created,
+                created_at: now,
+                completed_at: None,
+                client_reference: req.client_reference.clone(),
+                actual_received_amount: None,
+                expires_at: now + default_order_ttl_secs,
+                coordination_deadline: None,
+                filled_amount: 0,
+                sunk_setup_cost: 0,
+                settlement_failure_reason: None,
+                last_settlement: None,
+                settlement: Vec::new(),
+                destinations,
+                auto_refund_after: req.auto_refund_after,
+                timelocks: req.timelocks.unwrap_or(default_timelocks),
+            },
+        );
+        state
+            .expiry_index
+            .insert((now + default_order_ttl_secs, id.clone()));
+        if let Some(order) = state.orders.get(&id).cloned() {
+            crate::matching::index_for_pairing(&mut state, &order);
+        }
+
+        Ok((id, req.client_reference))
+    })
+    .map(|(id, client_reference)| {
+        crate::events::record_event(&id, now, "Created", "order created");
+        crate::indexes::add_to_indexes(maker, &id, client_reference.as_deref());
+        id
+    })
+}
+
+fn fingerprint_from_order(order: &SwapOrder) -> String {
+    format!(
+        "{}|{:?}|{:?}|{}|{}|{}|{}",
+        order.maker,
+        order.src_chain,
+        order.dst_chain,
+        order.src_token,
+        order.dst_token,
+        order.amount,
+        order.destination_address
+    )
+}
+
+/// Lets a maker cancel their own order while it's still unpaired, instead of
+/// waiting out the full timelock. Verifies the caller owns the order and
+/// that it hasn't been matched yet, then defers to `cancel_order_internal`
+/// for the actual refund/deindex/cooldown bookkeeping.
+pub fn cancel_unpaired_order_internal(caller: Principal, order_id: &str, now: u64) -> Result<(), SwapError> {
+    let status = STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+        if order.maker != caller {
+            return Err(SwapError::Unauthorized);
+        }
+        Ok(order.status.clone())
+    })?;
+    if status != SwapOrderStatus::Created {
+        return Err(SwapError::InvalidAmount(
+            "order can only be cancelled by its maker before it's paired".into(),
+        ));
+    }
+    cancel_order_internal(order_id, now)
+}
+
+#[ic_cdk::update]
+pub fn cancel_order(order_id: String) -> Result<(), SwapError> {
+    cancel_unpaired_order_internal(ic_cdk::caller(), &order_id, ic_cdk::api::time())
+}
+
+/// Cancels an order and starts the cancel/re-create cooldown for its
+/// parameters, so the same maker can't immediately re-submit an identical
+/// order to game pairing order or dodge a stale quote.
+pub fn cancel_order_internal(order_id: &str, now: u64) -> Result<(), SwapError> {
+    let cancelled_order = STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let order = state
+            .orders
+            .get_mut(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+        order.status = SwapOrderStatus::Cancelled;
+        let fingerprint = fingerprint_from_order(order);
+        let expires_at = order.expires_at;
+        let cancelled_order = order.clone();
+        state.recent_cancellations.insert(fingerprint, now);
+        state.expiry_index.remove(&(expires_at, order_id.to_string()));
+        crate::matching::deindex_from_pairing(&mut state, &cancelled_order);
+        Ok(cancelled_order)
+    })?;
+
+    let sunk = cancelled_order.sunk_setup_cost;
+    let detail = if sunk > 0 {
+        let net_refund = compute_net_refund(&cancelled_order);
+        format!(
+            "order cancelled; refunding {} of {} escrowed ({sunk} already spent on setup)",
+            net_refund.net_amount, net_refund.gross_amount
+        )
+    } else {
+        "order cancelled".to_string()
+    };
+    crate::events::record_event(order_id, now, "Cancelled", &detail);
+    Ok(())
+}
+
+/// Removes an order's entry from the expiry index, e.g. once it reaches a
+/// terminal state and no longer needs to be visited by the expiry sweep.
+pub fn remove_from_expiry_index(state: &mut crate::state::State, order_id: &str, expires_at: u64) {
+    state.expiry_index.remove(&(expires_at, order_id.to_string()));
+}
+
+/// Extends an order's expiry deadline, e.g. when a maker needs more time to
+/// complete a slow-settling leg before the order becomes refund-eligible.
+/// Keeps the expiry index in sync so the sweep doesn't visit the order under
+/// its stale deadline.
+pub fn extend_order_expiry_internal(
+    caller: Principal,
+    order_id: &str,
+    new_expires_at: u64,
+) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let order = state
+            .orders
+            .get_mut(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+        if order.maker != caller {
+            return Err(SwapError::Unauthorized);
+        }
+        let old_expires_at = order.expires_at;
+        order.expires_at = new_expires_at;
+        state.expiry_index.remove(&(old_expires_at, order_id.to_string()));
+        state.expiry_index.insert((new_expires_at, order_id.to_string()));
+        Ok(())
+    })
+}
+
+#[ic_cdk::update]
+pub fn extend_order_expiry(order_id: String, new_expires_at: u64) -> Result<(), SwapError> {
+    extend_order_expiry_internal(ic_cdk::caller(), &order_id, new_expires_at)
+}
+
+/// Admin-only: adjust the flat refund fee reserved for a chain.
+#[ic_cdk::update]
+pub fn set_refund_fee_for_chain(chain: Chain, fee: u128) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.refund_fee_by_chain.insert(chain, fee));
+    Ok(())
+}
+
+fn refund_fee_for_chain(chain: Chain) -> u128 {
+    STATE.with(|s| s.borrow().config.refund_fee_by_chain.get(&chain).copied().unwrap_or(0))
+}
+
+/// The amount actually returned to the maker on refund: the confirmed
+/// escrowed amount (falling back to the order's nominal `amount` if nothing
+/// was confirmed received yet) minus the source chain's refund fee headroom
+/// and any cost already irrecoverably spent setting up this order's escrow,
+/// saturating so it never fails even if the escrow exactly equals the
+/// nominal amount.
+pub fn refund_amount_due(order: &SwapOrder) -> u128 {
+    let escrowed = order.actual_received_amount.unwrap_or(order.amount);
+    escrowed
+        .saturating_sub(refund_fee_for_chain(order.src_chain))
+        .saturating_sub(order.sunk_setup_cost)
+}
+
+/// Query the amount that would actually be returned to the maker if this
+/// order were refunded right now.
+#[ic_cdk::query]
+pub fn get_refund_amount_due(order_id: String) -> Result<u128, SwapError> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(&order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.clone()))?;
+        Ok(refund_amount_due(order))
+    })
+}
+
+/// Breakdown of what a refund would actually pay out: the gross escrowed
+/// amount, the net amount after fees and sunk costs, and the gap between the
+/// two, so a client can see *why* a refund is less than the full escrow
+/// instead of just the final number.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NetRefund {
+    pub gross_amount: u128,
+    pub net_amount: u128,
+    pub shortfall: u128,
+}
+
+pub fn compute_net_refund(order: &SwapOrder) -> NetRefund {
+    let gross_amount = order.actual_received_amount.unwrap_or(order.amount);
+    let net_amount = refund_amount_due(order);
+    NetRefund {
+        gross_amount,
+        net_amount,
+        shortfall: gross_amount.saturating_sub(net_amount),
+    }
+}
+
+/// Query the full fee/sunk-cost breakdown behind `get_refund_amount_due`.
+#[ic_cdk::query]
+pub fn get_net_refund_breakdown(order_id: String) -> Result<NetRefund, SwapError> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(&order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.clone()))?;
+        Ok(compute_net_refund(order))
+    })
+}
+
+/// Refunds orders past their `expires_at` deadline plus the configured
+/// `refund_grace_secs`. The grace window gives a counterparty's in-flight
+/// claim on the other chain time to land before the maker's escrow is
+/// released back, so a refund submitted the instant the timelock passes
+/// can't race a claim that was already broadcast. Only visits orders that
+/// have reached `expires_at`, via the expiry index, rather than scanning
+/// every order on the canister each run; an order still within its grace
+/// window is left in the index so a later sweep picks it up again.
+pub fn sweep_expired_orders(now: u64) -> Vec<String> {
+    let due: Vec<String> = STATE.with(|s| {
+        s.borrow()
+            .expiry_index
+            .iter()
+            .take_while(|(expires_at, _)| *expires_at <= now)
+            .map(|(_, order_id)| order_id.clone())
+            .collect()
+    });
+
+    let mut refunded = Vec::new();
+    for order_id in due {
+        let refund_amount = STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let grace_secs = state.config.refund_grace_secs;
+            let order = state.orders.get_mut(&order_id)?;
+            let expires_at = order.expires_at;
+            let already_terminal = matches!(
+                order.status,
+                SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded
+            );
+            if !already_terminal && now < expires_at.saturating_add(grace_secs) {
+                return None;
+            }
+            let amount = if already_terminal { None } else { Some(refund_amount_due(order)) };
+            if !already_terminal {
+                order.status = SwapOrderStatus::Refunded;
+                let expired_order = order.clone();
+                crate::matching::deindex_from_pairing(&mut state, &expired_order);
+            }
+            state.expiry_index.remove(&(expires_at, order_id.clone()));
+            amount
+        });
+        if let Some(amount) = refund_amount {
+            refunded.push((order_id, amount));
+        }
+    }
+
+    for (order_id, amount) in &refunded {
+        crate::events::record_event(order_id, now, "Refunded", &format!("order expired; refunding {amount}"));
+    }
+    refunded.into_iter().map(|(order_id, _)| order_id).collect()
+}
+
+/// Lets anyone — not just the maker — refund a non-terminal order once its
+/// source-side public-cancellation window has elapsed (`created_at +
+/// timelocks.src_cancellation`). Matches the public-cancellation stage of a
+/// Fusion+-style escrow: an abandoned swap's funds don't have to wait on a
+/// single party to reclaim them.
+#[ic_cdk::update]
+pub fn trigger_refund(order_id: String) -> Result<u128, SwapError> {
+    trigger_refund_internal(&order_id, ic_cdk::api::time())
+}
+
+pub fn trigger_refund_internal(order_id: &str, now: u64) -> Result<u128, SwapError> {
+    let amount = STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let order = state
+            .orders
+            .get_mut(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+        if matches!(
+            order.status,
+            SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded
+        ) {
+            return Err(SwapError::InvalidAmount("order is already in a terminal state".into()));
+        }
+        let cancellation_opens_at = order.created_at.saturating_add(order.timelocks.src_cancellation);
+        if now < cancellation_opens_at {
+            return Err(SwapError::TimelockNotElapsed { available_at: cancellation_opens_at });
+        }
+        let amount = refund_amount_due(order);
+        order.status = SwapOrderStatus::Refunded;
+        let expires_at = order.expires_at;
+        let refunded_order = order.clone();
+        crate::matching::deindex_from_pairing(&mut state, &refunded_order);
+        state.expiry_index.remove(&(expires_at, order_id.to_string()));
+        Ok(amount)
+    })?;
+    crate::events::record_event(
+        order_id,
+        now,
+        "Refunded",
+        &format!("public cancellation window elapsed; refunding {amount}"),
+    );
+    Ok(amount)
+}
+
+/// Drops cancel/re-create cooldown entries (the reservation cache) older
+/// than `ttl_secs`, so it doesn't grow without bound. Returns the number of
+/// entries removed.
+pub fn prune_expired_reservation_cache(now: u64, ttl_secs: u64) -> usize {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let before = state.recent_cancellations.len();
+        state
+            .recent_cancellations
+            .retain(|_, &mut cancelled_at| now.saturating_sub(cancelled_at) <= ttl_secs);
+        before - state.recent_cancellations.len()
+    })
+}
+
+/// Admin-only: adjust the minimum safety-deposit ratio applied to new orders.
+#[ic_cdk::update]
+pub fn set_safety_deposit_bps(bps: u32) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.safety_deposit_bps = bps);
+    Ok(())
+}
+
+/// Admin-only: adjust how long a paired order has to reach a terminal state
+/// before `sweep_stalled_swaps` auto-refunds it.
+#[ic_cdk::update]
+pub fn set_coordination_timeout_secs(secs: u64) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.coordination_timeout_secs = secs);
+    Ok(())
+}
+
+/// Admin-only: toggle draining mode ahead of a planned upgrade. While
+/// draining, new orders are rejected but everything already in flight keeps
+/// running to completion/refund; call `get_inflight_count` to confirm it's
+/// safe to upgrade.
+#[ic_cdk::update]
+pub fn set_draining_mode(draining: bool) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.draining = draining);
+    Ok(())
+}
+
+/// Number of orders not yet in a terminal state, i.e. still relying on a
+/// settlement, refund, or cancellation to finish. What an operator polls
+/// after `set_draining_mode(true)` before it's safe to upgrade.
+#[ic_cdk::query]
+pub fn get_inflight_count() -> u64 {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| {
+                !matches!(
+                    o.status,
+                    SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded
+                )
+            })
+            .count() as u64
+    })
+}
+
+/// Refunds orders that were paired (or funded) but never reached a terminal
+/// state before their `coordination_deadline` passed, rather than tying up
+/// escrow indefinitely on a stalled counterparty or RPC. Returns the ids
+/// refunded. Called from the heartbeat.
+pub fn sweep_stalled_swaps(now: u64) -> Vec<String> {
+    let stalled: Vec<String> = STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| {
+                matches!(o.status, SwapOrderStatus::Paired | SwapOrderStatus::EscrowFunded)
+                    && o.coordination_deadline.is_some_and(|deadline| now >= deadline)
+            })
+            .map(|o| o.id.clone())
+            .collect()
+    });
+
+    for order_id in &stalled {
+        let refund_amount = STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let order = state.orders.get_mut(order_id)?;
+            let amount = refund_amount_due(order);
+            order.status = SwapOrderStatus::Refunded;
+            let expires_at = order.expires_at;
+            state.expiry_index.remove(&(expires_at, order_id.clone()));
+            Some(amount)
+        });
+        if let Some(amount) = refund_amount {
+            crate::events::record_event(
+                order_id,
+                now,
+                "Refunded",
+                &format!("coordination timeout exceeded; refunding {amount}"),
+            );
+        }
+    }
+
+    stalled
+}
+
+/// Auto-cancels and refunds unpaired (`Created`) orders past their own
+/// `auto_refund_after` window, for makers unwilling to wait out the much
+/// longer `expires_at` timelock for a match that may never come. Orders
+/// without a window are left alone until `expires_at`. Returns the ids
+/// refunded. Called from the heartbeat.
+pub fn sweep_auto_refund_orders(now: u64) -> Vec<String> {
+    let due: Vec<String> = STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| {
+                o.status == SwapOrderStatus::Created
+                    && o.auto_refund_after.is_some_and(|deadline| now >= deadline)
+            })
+            .map(|o| o.id.clone())
+            .collect()
+    });
+
+    for order_id in &due {
+        let _ = cancel_order_internal(order_id, now);
+    }
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    fn base_request() -> CreateOrderRequest {
+        CreateOrderRequest {
+            src_chain: Chain::ICP,
+            dst_chain: Chain::Ethereum,
+            src_token: "ICP".into(),
+            dst_token: "ETH".into(),
+            amount: 10_000,
+            destination_address: "0xdead".into(),
+            escrowed_safety_deposit: 100,
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            timelocks: None,
+        }
+    }
+
+    #[test]
+    fn required_deposit_scales_with_amount() {
+        assert_eq!(required_safety_deposit(1_000, 100), 10);
+        assert_eq!(required_safety_deposit(10_000, 100), 100);
+        assert_eq!(required_safety_deposit(10_000, 50), 50);
+    }
+
+    #[test]
+    fn order_creation_rejects_under_deposited_order() {
+        reset_state();
+        let mut req = base_request();
+        req.escrowed_safety_deposit = 5;
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0);
+        assert_eq!(
+            result,
+            Err(SwapError::InsufficientSafetyDeposit {
+                required: 100,
+                provided: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn order_creation_accepts_sufficient_deposit() {
+        reset_state();
+        let result =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn recreating_identical_order_during_cooldown_is_rejected() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id =
+            create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        cancel_order_internal(&order_id, 10).unwrap();
+
+        let result = create_cross_chain_swap_order_internal(maker, base_request(), 20);
+        assert_eq!(result, Err(SwapError::CooldownActive { remaining_secs: 50 }));
+    }
+
+    #[test]
+    fn order_creation_is_rejected_while_draining() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.draining = true);
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0);
+        assert_eq!(result, Err(SwapError::Draining));
+    }
+
+    #[test]
+    fn an_order_created_before_draining_can_still_be_cancelled_and_refunded_while_draining() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+
+        STATE.with(|s| s.borrow_mut().config.draining = true);
+
+        cancel_unpaired_order_internal(maker, &order_id, 10).unwrap();
+
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Cancelled);
+        });
+    }
+
+    #[test]
+    fn inflight_count_only_counts_non_terminal_orders() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        assert_eq!(get_inflight_count(), 1);
+
+        cancel_unpaired_order_internal(maker, &order_id, 10).unwrap();
+        assert_eq!(get_inflight_count(), 0);
+    }
+
+    #[test]
+    fn the_maker_can_cancel_their_own_unpaired_order() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+
+        cancel_unpaired_order_internal(maker, &order_id, 10).unwrap();
+
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Cancelled);
+        });
+    }
+
+    #[test]
+    fn a_paired_order_cannot_be_cancelled_through_the_unpaired_path() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&order_id).unwrap().status = SwapOrderStatus::Paired);
+
+        let result = cancel_unpaired_order_internal(maker, &order_id, 10);
+
+        assert!(result.is_err());
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Paired);
+        });
+    }
+
+    #[test]
+    fn a_non_maker_cannot_cancel_someone_elses_order() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let stranger = Principal::from_slice(&[1u8; 29]);
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+
+        let result = cancel_unpaired_order_internal(stranger, &order_id, 10);
+
+        assert_eq!(result, Err(SwapError::Unauthorized));
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Created);
+        });
+    }
+
+    #[test]
+    fn order_creation_rejects_a_same_chain_pair() {
+        reset_state();
+        let mut req = base_request();
+        req.dst_chain = Chain::ICP;
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0);
+        assert_eq!(
+            result,
+            Err(SwapError::UnsupportedChainPair {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::ICP,
+            })
+        );
+    }
+
+    #[test]
+    fn order_creation_accepts_a_solana_source() {
+        reset_state();
+        let mut req = base_request();
+        req.src_chain = Chain::Solana;
+        req.dst_chain = Chain::Ethereum;
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0).unwrap();
+        STATE.with(|s| {
+            let order = &s.borrow().orders[&order_id];
+            assert_eq!(order.src_chain, Chain::Solana);
+            assert_eq!(order.dst_chain, Chain::Ethereum);
+        });
+    }
+
+    #[test]
+    fn order_creation_accepts_an_evm_source() {
+        reset_state();
+        let mut req = base_request();
+        req.src_chain = Chain::Ethereum;
+        req.dst_chain = Chain::Solana;
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0).unwrap();
+        STATE.with(|s| {
+            let order = &s.borrow().orders[&order_id];
+            assert_eq!(order.src_chain, Chain::Ethereum);
+            assert_eq!(order.dst_chain, Chain::Solana);
+        });
+    }
+
+    #[test]
+    fn order_creation_rejects_a_dust_sized_amount_below_the_token_minimum() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().min_amount_by_token.insert("ICP".into(), 1_000_000));
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0);
+        assert_eq!(
+            result,
+            Err(SwapError::AmountBelowMinimum { token: "ICP".into(), minimum: 1_000_000, amount: 10_000 })
+        );
+    }
+
+    #[test]
+    fn order_creation_accepts_an_amount_within_the_configured_token_bounds() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.min_amount_by_token.insert("ICP".into(), 1_000);
+            state.max_amount_by_token.insert("ICP".into(), 1_000_000);
+        });
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn order_creation_rejects_paused_src_token() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().paused_tokens.insert("ICP".into(), true));
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0);
+        assert_eq!(result, Err(SwapError::TokenPaused("ICP".into())));
+    }
+
+    #[test]
+    fn pruning_removes_only_entries_past_ttl() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.recent_cancellations.insert("stale".into(), 0);
+            state.recent_cancellations.insert("fresh".into(), 90);
+        });
+
+        let removed = prune_expired_reservation_cache(100, 50);
+
+        assert_eq!(removed, 1);
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert!(!state.recent_cancellations.contains_key("stale"));
+            assert!(state.recent_cancellations.contains_key("fresh"));
+        });
+    }
+
+    #[test]
+    fn mixed_case_evm_destination_addresses_are_normalized_and_treated_as_equal() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let mut req = base_request();
+        req.destination_address = "0xABCDEF1234567890ABCDEF1234567890ABCDEF12".into();
+        let order_id = create_cross_chain_swap_order_internal(maker, req, 0).unwrap();
+        cancel_order_internal(&order_id, 10).unwrap();
+
+        let mut req_lowercase = base_request();
+        req_lowercase.destination_address = "0xabcdef1234567890abcdef1234567890abcdef12".into();
+        let result = create_cross_chain_swap_order_internal(maker, req_lowercase, 20);
+
+        // Same cooldown fingerprint despite differing case, so the re-create
+        // cooldown still applies instead of silently treating them as different orders.
+        assert_eq!(result, Err(SwapError::CooldownActive { remaining_secs: 50 }));
+    }
+
+    #[test]
+    fn a_stalled_swap_is_auto_refunded_after_the_coordination_timeout() {
+        reset_state();
+        let order_id =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0)
+                .unwrap();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let order = state.orders.get_mut(&order_id).unwrap();
+            order.status = SwapOrderStatus::Paired;
+            order.coordination_deadline = Some(100);
+        });
+
+        assert_eq!(sweep_stalled_swaps(50), Vec::<String>::new());
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Paired);
+        });
+
+        let swept = sweep_stalled_swaps(100);
+
+        assert_eq!(swept, vec![order_id.clone()]);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Refunded);
+        });
+    }
+
+    #[test]
+    fn an_order_with_an_auto_refund_window_is_refunded_once_it_elapses() {
+        reset_state();
+        let mut req = base_request();
+        req.auto_refund_after = Some(100);
+        let order_id =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0).unwrap();
+
+        assert_eq!(sweep_auto_refund_orders(50), Vec::<String>::new());
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Created);
+        });
+
+        let swept = sweep_auto_refund_orders(100);
+
+        assert_eq!(swept, vec![order_id.clone()]);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Cancelled);
+        });
+    }
+
+    #[test]
+    fn an_order_without_an_auto_refund_window_survives_until_the_hard_timelock() {
+        reset_state();
+        let order_id =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0)
+                .unwrap();
+
+        // Far past any reasonable auto-refund window, but `auto_refund_after`
+        // was never set, so only the hard `expires_at` timelock applies.
+        assert_eq!(sweep_auto_refund_orders(1_000_000), Vec::<String>::new());
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Created);
+        });
+    }
+
+    #[test]
+    fn a_paired_order_is_left_alone_by_the_auto_refund_sweep_even_past_its_window() {
+        reset_state();
+        let mut req = base_request();
+        req.auto_refund_after = Some(100);
+        let order_id =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0).unwrap();
+        STATE.with(|s| {
+            s.borrow_mut().orders.get_mut(&order_id).unwrap().status = SwapOrderStatus::Paired;
+        });
+
+        assert_eq!(sweep_auto_refund_orders(200), Vec::<String>::new());
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Paired);
+        });
+    }
+
+    #[test]
+    fn order_creation_is_rejected_once_the_pending_pairing_backlog_is_full() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.max_pending_pairing_backlog = 2);
+        let maker = Principal::anonymous();
+        create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        create_cross_chain_swap_order_internal(maker, base_request(), 1).unwrap();
+
+        let result = create_cross_chain_swap_order_internal(maker, base_request(), 2);
+
+        assert_eq!(result, Err(SwapError::BacklogFull { max: 2 }));
+        STATE.with(|s| assert_eq!(s.borrow().orders.len(), 2));
+    }
+
+    #[test]
+    fn backlog_count_only_considers_unpaired_created_orders() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.max_pending_pairing_backlog = 1);
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| {
+            s.borrow_mut().orders.get_mut(&order_id).unwrap().status = SwapOrderStatus::Paired;
+        });
+
+        // The backlog of still-`Created` orders is empty again, so creation succeeds.
+        let result = create_cross_chain_swap_order_internal(maker, base_request(), 1);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn expiry_sweep_visits_only_expired_orders() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let expired = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        let unexpired = create_cross_chain_swap_order_internal(maker, base_request(), 50).unwrap();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let old = state.orders.get_mut(&expired).unwrap().expires_at;
+            state.expiry_index.remove(&(old, expired.clone()));
+            state.orders.get_mut(&expired).unwrap().expires_at = 10;
+            state.expiry_index.insert((10, expired.clone()));
+            // Isolate index behavior from the grace-window feature under test below.
+            state.config.refund_grace_secs = 0;
+        });
+
+        let swept = sweep_expired_orders(20);
+
+        assert_eq!(swept, vec![expired.clone()]);
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&expired].status, SwapOrderStatus::Refunded);
+            assert_eq!(state.orders[&unexpired].status, SwapOrderStatus::Created);
+            assert!(!state.expiry_index.iter().any(|(_, id)| id == &expired));
+        });
+    }
+
+    #[test]
+    fn a_refund_is_withheld_until_the_grace_window_past_expiry_elapses() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let old = state.orders.get_mut(&order_id).unwrap().expires_at;
+            state.expiry_index.remove(&(old, order_id.clone()));
+            state.orders.get_mut(&order_id).unwrap().expires_at = 10;
+            state.expiry_index.insert((10, order_id.clone()));
+            state.config.refund_grace_secs = 100;
+        });
+
+        // Past expires_at but still inside the grace window: no refund yet,
+        // and the order stays in the index for a later sweep to retry.
+        assert_eq!(sweep_expired_orders(50), Vec::<String>::new());
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&order_id].status, SwapOrderStatus::Created);
+            assert!(state.expiry_index.contains(&(10, order_id.clone())));
+        });
+
+        // Once the grace window has elapsed, the same sweep refunds it.
+        let swept = sweep_expired_orders(110);
+        assert_eq!(swept, vec![order_id.clone()]);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Refunded);
+        });
+    }
+
+    #[test]
+    fn trigger_refund_is_rejected_before_the_cancellation_window_elapses() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&order_id).unwrap().timelocks.src_cancellation = 100);
+
+        let result = trigger_refund_internal(&order_id, 50);
+
+        assert_eq!(result, Err(SwapError::TimelockNotElapsed { available_at: 100 }));
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Created);
+        });
+    }
+
+    #[test]
+    fn trigger_refund_succeeds_for_any_caller_once_the_cancellation_window_elapses() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&order_id).unwrap().timelocks.src_cancellation = 100);
+
+        let refunded = trigger_refund_internal(&order_id, 100).unwrap();
+
+        assert!(refunded > 0);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Refunded);
+        });
+    }
+
+    #[test]
+    fn trigger_refund_rejects_an_order_already_in_a_terminal_state() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        cancel_unpaired_order_internal(maker, &order_id, 0).unwrap();
+
+        let result = trigger_refund_internal(&order_id, 1_000_000);
+
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn expiry_index_stays_consistent_after_an_extension() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id = create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+
+        extend_order_expiry_internal(maker, &order_id, 10_000).unwrap();
+
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&order_id].expires_at, 10_000);
+            assert!(state.expiry_index.contains(&(10_000, order_id.clone())));
+            assert_eq!(state.expiry_index.len(), 1);
+        });
+
+        // Sweeping at the old deadline must not touch the order now that
+        // it's been extended past it.
+        assert_eq!(sweep_expired_orders(base_request_ttl()), Vec::<String>::new());
+    }
+
+    fn base_request_ttl() -> u64 {
+        STATE.with(|s| s.borrow().config.default_order_ttl_secs)
+    }
+
+    #[test]
+    fn extension_is_rejected_for_a_non_maker_caller() {
+        reset_state();
+        let order_id =
+            create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0)
+                .unwrap();
+
+        let result =
+            extend_order_expiry_internal(Principal::from_slice(&[1; 29]), &order_id, 10_000);
+
+        assert_eq!(result, Err(SwapError::Unauthorized));
+    }
+
+    #[test]
+    fn recreating_identical_order_after_cooldown_succeeds() {
+        reset_state();
+        let maker = Principal::anonymous();
+        let order_id =
+            create_cross_chain_swap_order_internal(maker, base_request(), 0).unwrap();
+        cancel_order_internal(&order_id, 10).unwrap();
+
+        let result = create_cross_chain_swap_order_internal(maker, base_request(), 70);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn refund_amount_deducts_the_source_chain_fee_from_the_nominal_amount() {
+        reset_state();
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        let fee = STATE.with(|s| *s.borrow().config.refund_fee_by_chain.get(&Chain::ICP).unwrap());
+
+        let due = STATE.with(|s| refund_amount_due(&s.borrow().orders[&order_id]));
+
+        assert_eq!(due, base_request().amount - fee);
+    }
+
+    #[test]
+    fn refund_of_an_exactly_sized_escrow_succeeds_instead_of_erroring() {
+        reset_state();
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        STATE.with(|s| {
+            s.borrow_mut().orders.get_mut(&order_id).unwrap().actual_received_amount = Some(base_request().amount);
+        });
+
+        let due = get_refund_amount_due(order_id.clone()).unwrap();
+
+        assert!(due < base_request().amount);
+        assert!(due > 0);
+
+        let refunded = sweep_expired_orders(base_request_ttl());
+        assert_eq!(refunded, vec![order_id.clone()]);
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Refunded);
+        });
+    }
+
+    #[test]
+    fn refund_amount_never_underflows_even_if_the_fee_exceeds_the_escrow() {
+        reset_state();
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        STATE.with(|s| {
+            s.borrow_mut().config.refund_fee_by_chain.insert(Chain::ICP, base_request().amount * 2);
+        });
+
+        let due = get_refund_amount_due(order_id).unwrap();
+
+        assert_eq!(due, 0);
+    }
+
+    #[test]
+    fn a_stalled_swaps_refund_event_reports_the_fee_adjusted_amount() {
+        reset_state();
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        let fee = STATE.with(|s| *s.borrow().config.refund_fee_by_chain.get(&Chain::ICP).unwrap());
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let order = state.orders.get_mut(&order_id).unwrap();
+            order.status = SwapOrderStatus::Paired;
+            order.coordination_deadline = Some(0);
+        });
+
+        let stalled = sweep_stalled_swaps(0);
+
+        assert_eq!(stalled, vec![order_id.clone()]);
+        let events = crate::events::events_for(&order_id);
+        let expected_amount = base_request().amount - fee;
+        assert!(events.iter().any(|e| e.detail == format!("coordination timeout exceeded; refunding {expected_amount}")));
+    }
+
+    #[test]
+    fn cancelling_after_htlc_creation_deducts_the_sunk_setup_cost_from_the_refund() {
+        reset_state();
+        // A setup cost small enough to not exhaust the whole escrow, so the
+        // deduction is visible rather than saturating to zero.
+        STATE.with(|s| s.borrow_mut().config.htlc_setup_cost_by_chain.insert(Chain::Ethereum, 2_000));
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        let fee = STATE.with(|s| *s.borrow().config.refund_fee_by_chain.get(&Chain::ICP).unwrap());
+        crate::htlc::create_htlc_escrow(&order_id, vec![7u8; 32]).unwrap();
+
+        let net_refund = get_net_refund_breakdown(order_id.clone()).unwrap();
+
+        assert_eq!(net_refund.gross_amount, base_request().amount);
+        assert_eq!(net_refund.net_amount, base_request().amount - fee - 2_000);
+        assert_eq!(net_refund.shortfall, fee + 2_000);
+    }
+
+    #[test]
+    fn cancelling_before_any_setup_cost_is_spent_refunds_with_no_shortfall_beyond_the_flat_fee() {
+        reset_state();
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        let fee = STATE.with(|s| *s.borrow().config.refund_fee_by_chain.get(&Chain::ICP).unwrap());
+
+        cancel_order_internal(&order_id, 10).unwrap();
+
+        let events = crate::events::events_for(&order_id);
+        assert!(events.iter().any(|e| e.detail == "order cancelled"));
+        let net_refund = get_net_refund_breakdown(order_id).unwrap();
+        assert_eq!(net_refund.shortfall, fee);
+    }
+
+    #[test]
+    fn cancelling_after_htlc_creation_logs_the_shortfall_in_the_event() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.htlc_setup_cost_by_chain.insert(Chain::Ethereum, 2_000));
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), base_request(), 0).unwrap();
+        crate::htlc::create_htlc_escrow(&order_id, vec![7u8; 32]).unwrap();
+        let net_refund = get_net_refund_breakdown(order_id.clone()).unwrap();
+
+        cancel_order_internal(&order_id, 10).unwrap();
+
+        let events = crate::events::events_for(&order_id);
+        let expected_detail = format!(
+            "order cancelled; refunding {} of {} escrowed (2000 already spent on setup)",
+            net_refund.net_amount, net_refund.gross_amount
+        );
+        assert!(events.iter().any(|e| e.detail == expected_detail));
+    }
+
+    fn split_request(destinations: Vec<crate::types::PayoutDestinationRequest>) -> CreateOrderRequest {
+        let mut req = base_request();
+        req.destinations = Some(destinations);
+        req
+    }
+
+    #[test]
+    fn a_two_way_split_payout_summing_to_the_order_amount_is_accepted() {
+        reset_state();
+        let req = split_request(vec![
+            crate::types::PayoutDestinationRequest { address: "0xaaaa".into(), amount: 6_000 },
+            crate::types::PayoutDestinationRequest { address: "0xbbbb".into(), amount: 4_000 },
+        ]);
+        let order_id = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0).unwrap();
+
+        STATE.with(|s| {
+            let destinations = s.borrow().orders[&order_id].destinations.clone().unwrap();
+            assert_eq!(destinations.len(), 2);
+            assert_eq!(destinations[0].amount, 6_000);
+            assert_eq!(destinations[1].amount, 4_000);
+            assert!(destinations.iter().all(|d| !d.released));
+        });
+    }
+
+    #[test]
+    fn a_split_payout_that_does_not_sum_to_the_order_amount_is_rejected() {
+        reset_state();
+        let req = split_request(vec![
+            crate::types::PayoutDestinationRequest { address: "0xaaaa".into(), amount: 6_000 },
+            crate::types::PayoutDestinationRequest { address: "0xbbbb".into(), amount: 3_000 },
+        ]);
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0);
+        assert!(matches!(result, Err(SwapError::InvalidSplitPayout(_))));
+    }
+
+    #[test]
+    fn a_split_payout_with_an_address_of_the_wrong_chain_type_is_rejected() {
+        reset_state();
+        let req = split_request(vec![
+            crate::types::PayoutDestinationRequest { address: "not-an-evm-address".into(), amount: 10_000 },
+        ]);
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0);
+        assert!(matches!(result, Err(SwapError::InvalidSplitPayout(_))));
+    }
+
+    #[test]
+    fn an_empty_split_payout_is_rejected() {
+        reset_state();
+        let req = split_request(vec![]);
+        let result = create_cross_chain_swap_order_internal(Principal::anonymous(), req, 0);
+        assert!(matches!(result, Err(SwapError::InvalidSplitPayout(_))));
+    }
+
+    /// Drives a future to completion without pulling in an async-executor
+    /// dependency. Fine here because the allowance check's stub ledger
+    /// resolves on its first poll; this isn't a general-purpose executor.
+    fn block_on<F: std::future::Future>(mut fut: F) -> F::Output {
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let raw = RawWaker::new(std::ptr::null(), &VTABLE);
+        let waker = unsafe { Waker::from_raw(raw) };
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+        loop {
+            if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                return val;
+            }
+        }
+    }
+
+    #[test]
+    fn an_insufficient_allowance_leaves_no_order_in_the_store() {
+        reset_state();
+        let maker = Principal::anonymous();
+        STATE.with(|s| {
+            s.borrow_mut()
+                .icrc_ledger_canisters
+                .insert("ICP".into(), Principal::management_canister());
+        });
+
+        let result = block_on(create_cross_chain_swap_order_checked(
+            maker,
+            Principal::management_canister(),
+            base_request(),
+            0,
+        ));
+
+        assert!(matches!(result, Err(SwapError::InsufficientAllowance { .. })));
+        assert!(STATE.with(|s| s.borrow().orders.is_empty()));
+    }
+
+    #[test]
+    fn a_source_token_with_no_configured_ledger_skips_the_allowance_check() {
+        reset_state();
+        let result = block_on(create_cross_chain_swap_order_checked(
+            Principal::anonymous(),
+            Principal::management_canister(),
+            base_request(),
+            0,
+        ));
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+use candid::Principal;
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+use crate::types::{Chain, SwapOrderStatus};
+
+/// Admin-only: set the known available liquidity for a (chain, token) pair,
+/// used by the pre-flight balance check before pairing.
+#[ic_cdk::update]
+pub fn set_chain_liquidity(chain: Chain, token: String, available: u128) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    set_chain_liquidity_internal(chain, token, available);
+    Ok(())
+}
+
+fn set_chain_liquidity_internal(chain: Chain, token: String, available: u128) {
+    STATE.with(|s| s.borrow_mut().chain_liquidity.insert((chain, token), available));
+}
+
+fn available_liquidity(chain: Chain, token: &str) -> u128 {
+    STATE.with(|s| {
+        s.borrow()
+            .chain_liquidity
+            .get(&(chain, token.to_string()))
+            .copied()
+            .unwrap_or(0)
+    })
+}
+
+/// Admin-only: set the minimum amount of `token` on `chain` that must always
+/// remain after a withdrawal, so `withdraw_chain_liquidity` can never drain a
+/// chain to zero and break subsequent same-chain swaps.
+#[ic_cdk::update]
+pub fn set_min_chain_reserve(chain: Chain, token: String, min_reserve: u128) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().min_chain_reserve.insert((chain, token), min_reserve));
+    Ok(())
+}
+
+fn min_reserve_for(chain: Chain, token: &str) -> u128 {
+    STATE.with(|s| {
+        s.borrow()
+            .min_chain_reserve
+            .get(&(chain, token.to_string()))
+            .copied()
+            .unwrap_or(0)
+    })
+}
+
+/// Admin-only: withdraws `amount` of `token`'s known liquidity on `chain`
+/// (e.g. to sweep accrued yield off-chain), rejecting the withdrawal outright
+/// if it would drop `available_liquidity` below the chain's configured
+/// minimum reserve.
+#[ic_cdk::update]
+pub fn withdraw_chain_liquidity(chain: Chain, token: String, amount: u128) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    withdraw_chain_liquidity_internal(chain, token, amount)
+}
+
+fn withdraw_chain_liquidity_internal(chain: Chain, token: String, amount: u128) -> Result<(), SwapError> {
+    let available = available_liquidity(chain, &token);
+    if amount > available {
+        return Err(SwapError::InvalidAmount(format!(
+            "cannot withdraw {amount}, only {available} available"
+        )));
+    }
+    let remaining = available - amount;
+    let min_reserve = min_reserve_for(chain, &token);
+    if remaining < min_reserve {
+        return Err(SwapError::BelowMinimumReserve { chain, token, min_reserve, remaining });
+    }
+    STATE.with(|s| s.borrow_mut().chain_liquidity.insert((chain, token), remaining));
+    Ok(())
+}
+
+pub(crate) fn pair_order_internal(order_id: &str, now: u64) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if !state.config.pairing_enabled {
+            return Err(SwapError::PairingPaused);
+        }
+
+        let require_check = state.config.require_preflight_balance_check;
+        let coordination_timeout = state.config.coordination_timeout_secs;
+        let order = state
+            .orders
+            .get_mut(order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.to_string()))?;
+
+        if require_check {
+            let available = available_liquidity(order.dst_chain, &order.dst_token);
+            if available < order.amount {
+                return Err(SwapError::InsufficientDestinationLiquidity {
+                    chain: order.dst_chain,
+                    token: order.dst_token.clone(),
+                    required: order.amount,
+                    available,
+                });
+            }
+        }
+
+        order.status = SwapOrderStatus::Paired;
+        order.coordination_deadline = Some(now + coordination_timeout);
+        let paired_order = order.clone();
+        crate::matching::deindex_from_pairing(&mut state, &paired_order);
+        Ok(())
+    })
+}
+
+/// Pairs an order with a resolver/taker, after optionally verifying the
+/// destination chain can actually cover the payout. Skippable via
+/// `require_preflight_balance_check` for emergencies/testing. Rejected
+/// outright while `pairing_enabled` is off; the order stays `Created` and is
+/// picked up by `sweep_pending_pairing` once pairing resumes.
+#[ic_cdk::update]
+pub fn pair_order(order_id: String, _resolver: Principal) -> Result<(), SwapError> {
+    pair_order_internal(&order_id, ic_cdk::api::time())
+}
+
+/// Attempts to pair every order still waiting, e.g. right after pairing is
+/// re-enabled following an incident. Orders that still can't be paired
+/// (insufficient liquidity, etc.) are left untouched for a future sweep.
+/// Returns the ids that were successfully paired.
+pub fn sweep_pending_pairing() -> Vec<String> {
+    sweep_pending_pairing_internal(ic_cdk::api::time())
+}
+
+fn sweep_pending_pairing_internal(now: u64) -> Vec<String> {
+    let pending: Vec<String> = STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| o.status == SwapOrderStatus::Created)
+            .map(|o| o.id.clone())
+            .collect()
+    });
+
+    pending.into_iter().filter(|id| pair_order_internal(id, now).is_ok()).collect()
+}
+
+/// Admin-only: pause or resume order pairing. Re-enabling immediately sweeps
+/// the backlog of orders that accumulated while it was paused.
+#[ic_cdk::update]
+pub fn set_pairing_enabled(enabled: bool) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.pairing_enabled = enabled);
+    if enabled {
+        sweep_pending_pairing();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CreateOrderRequest;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    fn create_order() -> String {
+        crate::orders::create_cross_chain_swap_order_internal(
+            Principal::anonymous(),
+            CreateOrderRequest {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pairing_rejected_without_enough_destination_liquidity() {
+        reset_state();
+        let order_id = create_order();
+        let result = pair_order_internal(&order_id, 0);
+        assert!(matches!(
+            result,
+            Err(SwapError::InsufficientDestinationLiquidity { .. })
+        ));
+    }
+
+    #[test]
+    fn pairing_succeeds_once_liquidity_is_sufficient() {
+        reset_state();
+        let order_id = create_order();
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 20_000);
+
+        pair_order_internal(&order_id, 0).unwrap();
+
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Paired);
+        });
+    }
+
+    #[test]
+    fn pairing_sets_a_coordination_deadline_based_on_the_configured_timeout() {
+        reset_state();
+        let order_id = create_order();
+        STATE.with(|s| s.borrow_mut().config.coordination_timeout_secs = 1_800);
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 20_000);
+
+        pair_order_internal(&order_id, 1_000).unwrap();
+
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].coordination_deadline, Some(2_800));
+        });
+    }
+
+    #[test]
+    fn orders_accumulate_unpaired_while_pairing_is_disabled() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.pairing_enabled = false);
+        let order_id = create_order();
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 20_000);
+
+        let result = pair_order_internal(&order_id, 0);
+
+        assert_eq!(result, Err(SwapError::PairingPaused));
+        STATE.with(|s| {
+            assert_eq!(s.borrow().orders[&order_id].status, SwapOrderStatus::Created);
+        });
+    }
+
+    fn create_order_for_token(dst_token: &str) -> String {
+        crate::orders::create_cross_chain_swap_order_internal(
+            Principal::anonymous(),
+            CreateOrderRequest {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: dst_token.to_string(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sweep_pairs_the_backlog_once_re_enabled() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.pairing_enabled = false);
+        let paired_later = create_order_for_token("ETH");
+        let still_short_on_liquidity = create_order_for_token("USDC");
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 20_000);
+
+        let result = pair_order_internal(&paired_later, 0);
+        assert_eq!(result, Err(SwapError::PairingPaused));
+
+        STATE.with(|s| s.borrow_mut().config.pairing_enabled = true);
+        let swept = sweep_pending_pairing_internal(0);
+
+        assert_eq!(swept, vec![paired_later.clone()]);
+        STATE.with(|s| {
+            let state = s.borrow();
+            assert_eq!(state.orders[&paired_later].status, SwapOrderStatus::Paired);
+            assert_eq!(state.orders[&still_short_on_liquidity].status, SwapOrderStatus::Created);
+        });
+    }
+
+    #[test]
+    fn a_withdrawal_that_would_breach_the_minimum_reserve_is_rejected() {
+        reset_state();
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 1_000);
+        STATE.with(|s| s.borrow_mut().min_chain_reserve.insert((Chain::Ethereum, "ETH".into()), 400));
+
+        let result = withdraw_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 700);
+
+        assert_eq!(
+            result,
+            Err(SwapError::BelowMinimumReserve {
+                chain: Chain::Ethereum,
+                token: "ETH".into(),
+                min_reserve: 400,
+                remaining: 300,
+            })
+        );
+        assert_eq!(available_liquidity(Chain::Ethereum, "ETH"), 1_000);
+    }
+
+    #[test]
+    fn a_withdrawal_that_respects_the_minimum_reserve_succeeds() {
+        reset_state();
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 1_000);
+        STATE.with(|s| s.borrow_mut().min_chain_reserve.insert((Chain::Ethereum, "ETH".into()), 400));
+
+        withdraw_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 500).unwrap();
+
+        assert_eq!(available_liquidity(Chain::Ethereum, "ETH"), 500);
+    }
+
+    #[test]
+    fn withdrawing_more_than_is_available_is_rejected_before_the_reserve_check() {
+        reset_state();
+        set_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 100);
+
+        let result = withdraw_chain_liquidity_internal(Chain::Ethereum, "ETH".into(), 500);
+
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+}
@@ -0,0 +1,301 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+use crate::types::Chain;
+
+/// Parameters governing how a pool rebalances liquidity across chains to
+/// chase yield. All rates are fractions in [0, 1].
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct YieldStrategy {
+    pub target_utilization: f64,
+    pub min_yield_improvement: f64,
+    pub rebalance_interval_secs: u64,
+}
+
+impl Default for YieldStrategy {
+    fn default() -> Self {
+        Self {
+            target_utilization: 0.8,
+            min_yield_improvement: 0.01,
+            rebalance_interval_secs: 3_600,
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct UnifiedLiquidityPool {
+    pub id: String,
+    pub token_symbol: String,
+    pub chains: Vec<Chain>,
+    pub yield_strategy: YieldStrategy,
+    pub apy_history: Vec<ApySnapshot>,
+    pub yield_accruals: Vec<YieldAccrual>,
+    /// (timestamp, utilization, apy_bps) of the last sample accrual was
+    /// computed from. `None` until the first sample is recorded.
+    last_utilization_sample: Option<(u64, f64, u32)>,
+}
+
+/// A realized-yield increment accrued between two utilization samples.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct YieldAccrual {
+    pub timestamp: u64,
+    pub realized_yield: f64,
+}
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// A single point-in-time APY observation for a pool.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ApySnapshot {
+    pub timestamp: u64,
+    pub apy_bps: u32,
+}
+
+#[ic_cdk::update]
+pub fn create_unified_liquidity_pool(token_symbol: String, chain: Chain) -> String {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let seq = state.next_pool_seq;
+        state.next_pool_seq += 1;
+        let id = format!("pool-{seq}");
+        state.pools.insert(
+            id.clone(),
+            UnifiedLiquidityPool {
+                id: id.clone(),
+                token_symbol,
+                chains: vec![chain],
+                yield_strategy: YieldStrategy::default(),
+                apy_history: Vec::new(),
+                yield_accruals: Vec::new(),
+                last_utilization_sample: None,
+            },
+        );
+        id
+    })
+}
+
+/// Accrues time-weighted realized yield for the period since the pool's last
+/// recorded utilization sample (at the utilization and APY that applied over
+/// that period), then records `utilization`/`apy_bps` as the new sample.
+/// Call this on every pool mutation that changes utilization or APY, so
+/// `get_pool_realized_yield` reflects actual exposure over time rather than
+/// a point-in-time APY snapshot.
+pub fn accrue_pool_yield(
+    pool_id: &str,
+    timestamp: u64,
+    utilization: f64,
+    apy_bps: u32,
+) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let pool = state
+            .pools
+            .get_mut(pool_id)
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.to_string()))?;
+
+        if let Some((last_ts, last_utilization, last_apy_bps)) = pool.last_utilization_sample {
+            let elapsed_secs = timestamp.saturating_sub(last_ts) as f64;
+            let annual_rate = last_apy_bps as f64 / 10_000.0;
+            let realized_yield = last_utilization * annual_rate * (elapsed_secs / SECONDS_PER_YEAR);
+            pool.yield_accruals.push(YieldAccrual { timestamp, realized_yield });
+        }
+
+        pool.last_utilization_sample = Some((timestamp, utilization, apy_bps));
+        Ok(())
+    })
+}
+
+/// Sums realized yield accrued at or after `since`.
+#[ic_cdk::query]
+pub fn get_pool_realized_yield(pool_id: String, since: u64) -> Result<f64, SwapError> {
+    STATE.with(|s| {
+        s.borrow()
+            .pools
+            .get(&pool_id)
+            .map(|p| {
+                p.yield_accruals
+                    .iter()
+                    .filter(|a| a.timestamp >= since)
+                    .map(|a| a.realized_yield)
+                    .sum()
+            })
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.clone()))
+    })
+}
+
+/// Records an APY observation for a pool, e.g. from a periodic timer.
+pub fn record_pool_apy(pool_id: &str, timestamp: u64, apy_bps: u32) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let pool = state
+            .pools
+            .get_mut(pool_id)
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.to_string()))?;
+        pool.apy_history.push(ApySnapshot { timestamp, apy_bps });
+        Ok(())
+    })
+}
+
+/// Returns the recorded APY history for a pool, oldest first.
+#[ic_cdk::query]
+pub fn get_pool_apy_history(pool_id: String) -> Result<Vec<ApySnapshot>, SwapError> {
+    STATE.with(|s| {
+        s.borrow()
+            .pools
+            .get(&pool_id)
+            .map(|p| p.apy_history.clone())
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.clone()))
+    })
+}
+
+#[ic_cdk::update]
+pub fn add_chain_to_pool(pool_id: String, chain: Chain) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let pool = state
+            .pools
+            .get_mut(&pool_id)
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.clone()))?;
+        if !pool.chains.contains(&chain) {
+            pool.chains.push(chain);
+        }
+        Ok(())
+    })
+}
+
+/// Rejects a `YieldStrategy` whose fields fall outside sane operating bounds,
+/// so corrupted optimization math (e.g. `target_utilization: 2.0`) can never
+/// reach the pool's rebalancing logic.
+pub fn validate_yield_strategy(strategy: &YieldStrategy) -> Result<(), SwapError> {
+    if !(0.0..=1.0).contains(&strategy.target_utilization) {
+        return Err(SwapError::InvalidAmount(
+            "target_utilization must be within [0, 1]".into(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&strategy.min_yield_improvement) {
+        return Err(SwapError::InvalidAmount(
+            "min_yield_improvement must be within [0, 1]".into(),
+        ));
+    }
+    if strategy.rebalance_interval_secs == 0 {
+        return Err(SwapError::InvalidAmount(
+            "rebalance_interval_secs must be positive".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Admin-only: set a pool's yield-optimization parameters after validating them.
+#[ic_cdk::update]
+pub fn set_pool_yield_strategy(pool_id: String, strategy: YieldStrategy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    validate_yield_strategy(&strategy)?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let pool = state
+            .pools
+            .get_mut(&pool_id)
+            .ok_or_else(|| SwapError::OrderNotFound(pool_id.clone()))?;
+        pool.yield_strategy = strategy;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_default_strategy() {
+        assert!(validate_yield_strategy(&YieldStrategy::default()).is_ok());
+    }
+
+    #[test]
+    fn rejects_utilization_above_one() {
+        let strategy = YieldStrategy {
+            target_utilization: 2.0,
+            ..YieldStrategy::default()
+        };
+        assert!(validate_yield_strategy(&strategy).is_err());
+    }
+
+    #[test]
+    fn rejects_negative_min_yield_improvement() {
+        let strategy = YieldStrategy {
+            min_yield_improvement: -5.0,
+            ..YieldStrategy::default()
+        };
+        assert!(validate_yield_strategy(&strategy).is_err());
+    }
+
+    #[test]
+    fn rejects_zero_rebalance_interval() {
+        let strategy = YieldStrategy {
+            rebalance_interval_secs: 0,
+            ..YieldStrategy::default()
+        };
+        assert!(validate_yield_strategy(&strategy).is_err());
+    }
+
+    #[test]
+    fn apy_history_accumulates_in_recorded_order() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let pool_id = create_unified_liquidity_pool("ckBTC".into(), Chain::ICP);
+        record_pool_apy(&pool_id, 100, 500).unwrap();
+        record_pool_apy(&pool_id, 200, 550).unwrap();
+
+        let history = get_pool_apy_history(pool_id).unwrap();
+        assert_eq!(
+            history,
+            vec![
+                ApySnapshot { timestamp: 100, apy_bps: 500 },
+                ApySnapshot { timestamp: 200, apy_bps: 550 },
+            ]
+        );
+    }
+
+    #[test]
+    fn apy_history_for_unknown_pool_errors() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        assert!(get_pool_apy_history("pool-missing".into()).is_err());
+    }
+
+    #[test]
+    fn realized_yield_matches_the_time_weighted_integral() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let pool_id = create_unified_liquidity_pool("ckBTC".into(), Chain::ICP);
+        let year = 365 * 24 * 60 * 60;
+
+        // First sample just anchors the series; no prior period to accrue over.
+        accrue_pool_yield(&pool_id, 0, 0.5, 1_000).unwrap();
+        // Held 50% utilized at 10% APY for exactly one year: 0.5 * 0.10 * 1 = 0.05.
+        accrue_pool_yield(&pool_id, year, 0.8, 1_000).unwrap();
+        // Held 80% utilized at 10% APY for exactly one more year: 0.8 * 0.10 * 1 = 0.08.
+        accrue_pool_yield(&pool_id, 2 * year, 0.8, 1_000).unwrap();
+
+        let realized = get_pool_realized_yield(pool_id, 0).unwrap();
+        assert!((realized - 0.13).abs() < 1e-9);
+    }
+
+    #[test]
+    fn realized_yield_since_excludes_earlier_accruals() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let pool_id = create_unified_liquidity_pool("ckBTC".into(), Chain::ICP);
+        let year = 365 * 24 * 60 * 60;
+
+        accrue_pool_yield(&pool_id, 0, 0.5, 1_000).unwrap();
+        accrue_pool_yield(&pool_id, year, 0.8, 1_000).unwrap();
+        accrue_pool_yield(&pool_id, 2 * year, 0.8, 1_000).unwrap();
+
+        let realized = get_pool_realized_yield(pool_id, year + 1).unwrap();
+        assert!((realized - 0.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn realized_yield_for_unknown_pool_errors() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        assert!(get_pool_realized_yield("pool-missing".into(), 0).is_err());
+    }
+}
@@ -0,0 +1,905 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::events::OrderEvent;
+use crate::htlc::HTLCEscrow;
+use crate::state::STATE;
+use crate::types::{SwapOrder, SwapOrderStatus, SwapSummary};
+
+/// A single view merging an order with its HTLC escrows and audit-trail
+/// events, so a dashboard doesn't need to stitch together three calls.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OrderDetail {
+    pub order: SwapOrder,
+    pub htlcs: Vec<HTLCEscrow>,
+    pub events: Vec<OrderEvent>,
+}
+
+#[ic_cdk::query]
+pub fn get_order_detail(order_id: String) -> Result<OrderDetail, SwapError> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.clone()))?;
+        let htlcs = state.htlcs.get(&order_id).cloned().unwrap_or_default();
+        Ok(OrderDetail {
+            order,
+            htlcs,
+            events: crate::events::events_for(&order_id),
+        })
+    })
+}
+
+/// Maximum number of order ids accepted by a single `get_order_statuses` call.
+pub const MAX_BULK_STATUS_IDS: usize = 200;
+
+/// Public, privacy-preserving feed of the most recently completed swaps.
+/// Never includes principals, destination addresses, or secrets.
+#[ic_cdk::query]
+pub fn get_recent_swaps(limit: u64) -> Vec<SwapSummary> {
+    get_recent_swaps_internal(limit)
+}
+
+pub fn get_recent_swaps_internal(limit: u64) -> Vec<SwapSummary> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let mut completed: Vec<&SwapOrder> = state
+            .orders
+            .values()
+            .filter(|o| o.status == SwapOrderStatus::Completed)
+            .collect();
+        completed.sort_by(|a, b| {
+            b.completed_at
+                .unwrap_or(b.created_at)
+                .cmp(&a.completed_at.unwrap_or(a.created_at))
+        });
+        completed
+            .into_iter()
+            .take(limit as usize)
+            .map(SwapSummary::from)
+            .collect()
+    })
+}
+
+/// Returns the caller's orders that are past their expiry and still
+/// unsettled, i.e. eligible for a refund.
+#[ic_cdk::query]
+pub fn get_refundable_orders() -> Vec<SwapOrder> {
+    let caller = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    get_refundable_orders_internal(caller, now)
+}
+
+pub fn get_refundable_orders_internal(caller: candid::Principal, now: u64) -> Vec<SwapOrder> {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| o.maker == caller)
+            .filter(|o| o.expires_at <= now)
+            .filter(|o| {
+                !matches!(
+                    o.status,
+                    SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded
+                )
+            })
+            .cloned()
+            .collect()
+    })
+}
+
+/// Exposes every admin-configurable tunable in one call, so operators and
+/// dashboards don't need a separate getter per setting.
+#[ic_cdk::query]
+pub fn get_canister_config() -> crate::config::CanisterConfig {
+    STATE.with(|s| s.borrow().config.clone())
+}
+
+/// Finds orders carrying the given integrator-supplied `client_reference`.
+#[ic_cdk::query]
+pub fn find_orders_by_reference(client_reference: String) -> Vec<SwapOrder> {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|o| o.client_reference.as_deref() == Some(client_reference.as_str()))
+            .cloned()
+            .collect()
+    })
+}
+
+/// Looks up the status of each requested order id in a single call, returning
+/// `None` for ids that don't exist. Dashboards tracking many orders use this
+/// instead of one query per order.
+#[ic_cdk::query]
+pub fn get_order_statuses(ids: Vec<String>) -> Result<Vec<(String, Option<SwapOrderStatus>)>, SwapError> {
+    if ids.len() > MAX_BULK_STATUS_IDS {
+        return Err(SwapError::TooManyIds {
+            max: MAX_BULK_STATUS_IDS,
+            provided: ids.len(),
+        });
+    }
+    Ok(STATE.with(|s| {
+        let state = s.borrow();
+        ids.into_iter()
+            .map(|id| {
+                let status = state.orders.get(&id).map(|o| o.status.clone());
+                (id, status)
+            })
+            .collect()
+    }))
+}
+
+/// The action a keeper should take next on an order surfaced by
+/// `get_orders_needing_action`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecommendedAction {
+    Claim,
+    Refund,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ActionItem {
+    pub order_id: String,
+    pub action: RecommendedAction,
+}
+
+/// One-stop scan for keeper bots: every order that's either claimable (both
+/// HTLCs funded, awaiting the claim) or refundable (past its coordination
+/// deadline and still unsettled), so a keeper doesn't need to poll every
+/// order individually to find actionable ones.
+#[ic_cdk::query]
+pub fn get_orders_needing_action() -> Vec<ActionItem> {
+    get_orders_needing_action_internal(ic_cdk::api::time())
+}
+
+pub fn get_orders_needing_action_internal(now: u64) -> Vec<ActionItem> {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter_map(|order| {
+                if order.status == SwapOrderStatus::EscrowFunded {
+                    Some(ActionItem {
+                        order_id: order.id.clone(),
+                        action: RecommendedAction::Claim,
+                    })
+                } else if order.expires_at <= now
+                    && !matches!(
+                        order.status,
+                        SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded
+                    )
+                {
+                    Some(ActionItem {
+                        order_id: order.id.clone(),
+                        action: RecommendedAction::Refund,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// A `(source_chain, source_token) -> (destination_chain, destination_token)`
+/// direction that an order can currently be created for.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SwapRoute {
+    pub src_chain: crate::types::Chain,
+    pub src_token: String,
+    pub dst_chain: crate::types::Chain,
+    pub dst_token: String,
+}
+
+/// Every currently-creatable route, derived from registered liquidity pools
+/// rather than hardcoded, so a frontend doesn't drift out of sync with which
+/// chains a token actually supports. A pool spanning `[ICP, Ethereum, Base]`
+/// for token `"USDC"` yields a route for each ordered pair of its chains.
+/// Routes through a paused token or an unhealthy chain are excluded, since an
+/// order created for them would be rejected anyway.
+#[ic_cdk::query]
+pub fn get_supported_routes() -> Vec<SwapRoute> {
+    get_supported_routes_internal()
+}
+
+pub fn get_supported_routes_internal() -> Vec<SwapRoute> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let mut routes = Vec::new();
+        for pool in state.pools.values() {
+            if crate::tokens::is_token_paused(&pool.token_symbol) {
+                continue;
+            }
+            for &src_chain in &pool.chains {
+                if !crate::chains::is_chain_healthy(src_chain) {
+                    continue;
+                }
+                for &dst_chain in &pool.chains {
+                    if dst_chain == src_chain || !crate::chains::is_chain_healthy(dst_chain) {
+                        continue;
+                    }
+                    routes.push(SwapRoute {
+                        src_chain,
+                        src_token: pool.token_symbol.clone(),
+                        dst_chain,
+                        dst_token: pool.token_symbol.clone(),
+                    });
+                }
+            }
+        }
+        routes
+    })
+}
+
+/// A privacy-preserving view of an open order for `export_order_book_snapshot`:
+/// no maker principal, destination address, or secrets.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PublicOrder {
+    pub id: String,
+    pub src_chain: crate::types::Chain,
+    pub dst_chain: crate::types::Chain,
+    pub src_token: String,
+    pub dst_token: String,
+    pub amount: u128,
+    pub filled_amount: u128,
+    pub status: SwapOrderStatus,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+fn to_public_order(order: &SwapOrder) -> PublicOrder {
+    PublicOrder {
+        id: order.id.clone(),
+        src_chain: order.src_chain,
+        dst_chain: order.dst_chain,
+        src_token: order.src_token.clone(),
+        dst_token: order.dst_token.clone(),
+        amount: order.amount,
+        filled_amount: order.filled_amount,
+        status: order.status.clone(),
+        created_at: order.created_at,
+        expires_at: order.expires_at,
+    }
+}
+
+/// An order is still "open" (part of the live order book) until it reaches a
+/// terminal status.
+fn is_open_order(status: &SwapOrderStatus) -> bool {
+    !matches!(
+        status,
+        SwapOrderStatus::Completed | SwapOrderStatus::Cancelled | SwapOrderStatus::Refunded | SwapOrderStatus::Failed
+    )
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBookSnapshot {
+    pub timestamp: u64,
+    pub orders: Vec<PublicOrder>,
+}
+
+/// Caps a single `export_order_book_snapshot` page so the response body
+/// can't grow past the IC's per-call reply size limit on a large order book.
+pub const MAX_SNAPSHOT_PAGE_SIZE: usize = 500;
+
+/// Exports a page of the open order book for off-chain analytics, with
+/// sensitive fields redacted. Orders are paginated in a stable id order via
+/// `offset`/`limit` (capped at `MAX_SNAPSHOT_PAGE_SIZE`) so a book too large
+/// for one response can still be exported in full across repeated calls.
+#[ic_cdk::query]
+pub fn export_order_book_snapshot(offset: u64, limit: u64) -> OrderBookSnapshot {
+    export_order_book_snapshot_internal(offset, limit, ic_cdk::api::time())
+}
+
+pub fn export_order_book_snapshot_internal(offset: u64, limit: u64, now: u64) -> OrderBookSnapshot {
+    let limit = (limit as usize).min(MAX_SNAPSHOT_PAGE_SIZE);
+    let orders = STATE.with(|s| {
+        let state = s.borrow();
+        let mut open: Vec<&SwapOrder> = state.orders.values().filter(|o| is_open_order(&o.status)).collect();
+        open.sort_by(|a, b| a.id.cmp(&b.id));
+        open.into_iter().skip(offset as usize).take(limit).map(to_public_order).collect()
+    });
+    OrderBookSnapshot { timestamp: now, orders }
+}
+
+/// Maximum page size accepted by `list_orders_paged`/`list_htlcs_paged`.
+/// Unlike `export_order_book_snapshot`'s redacted pages, these return full
+/// records, so the cap is tighter and exceeding it is a hard error rather
+/// than a silent clamp — a caller relying on `limit` to bound response size
+/// should find out immediately if it asked for more than that.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Lists every order (any status, unredacted), sorted deterministically by
+/// `created_at` then `id` so a caller paging through with a growing `offset`
+/// gets a stable view even as new orders are created between calls. Returns
+/// the page alongside the total number of orders, so a caller knows when
+/// it's reached the end without guessing from a short page.
+#[ic_cdk::query]
+pub fn list_orders_paged(offset: u64, limit: u64) -> Result<(Vec<SwapOrder>, u64), SwapError> {
+    list_orders_paged_internal(offset, limit)
+}
+
+pub fn list_orders_paged_internal(offset: u64, limit: u64) -> Result<(Vec<SwapOrder>, u64), SwapError> {
+    if limit > MAX_PAGE_SIZE {
+        return Err(SwapError::InvalidAmount(format!(
+            "limit {limit} exceeds the maximum page size of {MAX_PAGE_SIZE}"
+        )));
+    }
+    Ok(STATE.with(|s| {
+        let state = s.borrow();
+        let mut orders: Vec<&SwapOrder> = state.orders.values().collect();
+        orders.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        let total = orders.len() as u64;
+        let page = orders.into_iter().skip(offset as usize).take(limit as usize).cloned().collect();
+        (page, total)
+    }))
+}
+
+/// Lists every HTLC escrow across every order, sorted by the owning order's
+/// `created_at` then its `order_id` (escrows within the same order keep
+/// their creation order, oldest fill segment first). An order's partial
+/// fills (see `matching::fill_order_internal`) can leave it with several
+/// escrows, so this flattens `State::htlcs`' per-order lists into one page
+/// rather than paginating by order and hiding the rest of that order's
+/// escrows.
+#[ic_cdk::query]
+pub fn list_htlcs_paged(offset: u64, limit: u64) -> Result<(Vec<HTLCEscrow>, u64), SwapError> {
+    list_htlcs_paged_internal(offset, limit)
+}
+
+pub fn list_htlcs_paged_internal(offset: u64, limit: u64) -> Result<(Vec<HTLCEscrow>, u64), SwapError> {
+    if limit > MAX_PAGE_SIZE {
+        return Err(SwapError::InvalidAmount(format!(
+            "limit {limit} exceeds the maximum page size of {MAX_PAGE_SIZE}"
+        )));
+    }
+    Ok(STATE.with(|s| {
+        let state = s.borrow();
+        let mut order_ids: Vec<&String> = state.htlcs.keys().collect();
+        order_ids.sort_by(|a, b| {
+            let created_a = state.orders.get(*a).map(|o| o.created_at).unwrap_or(0);
+            let created_b = state.orders.get(*b).map(|o| o.created_at).unwrap_or(0);
+            created_a.cmp(&created_b).then_with(|| a.cmp(b))
+        });
+        let escrows: Vec<HTLCEscrow> = order_ids
+            .into_iter()
+            .flat_map(|order_id| state.htlcs[order_id].iter().cloned())
+            .collect();
+        let total = escrows.len() as u64;
+        let page = escrows.into_iter().skip(offset as usize).take(limit as usize).collect();
+        (page, total)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Chain, SwapOrder};
+    use candid::Principal;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    fn completed_order(id: &str, completed_at: u64) -> SwapOrder {
+        SwapOrder {
+            id: id.to_string(),
+            maker: Principal::anonymous(),
+            src_chain: Chain::ICP,
+            dst_chain: Chain::Ethereum,
+            src_token: "ICP".into(),
+            dst_token: "ETH".into(),
+            amount: 1_000,
+            destination_address: "0xsecretdestination".into(),
+            safety_deposit: 10,
+            status: SwapOrderStatus::Completed,
+            created_at: completed_at,
+            completed_at: Some(completed_at),
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            actual_received_amount: None,
+            expires_at: completed_at + 1,
+            coordination_deadline: None,
+            filled_amount: 0,
+            sunk_setup_cost: 0,
+            settlement_failure_reason: None,
+            last_settlement: None,
+            settlement: Vec::new(),
+            timelocks: crate::types::Timelocks {
+                src_withdrawal: 0,
+                src_cancellation: 3_600,
+                dst_withdrawal: 0,
+                dst_cancellation: 1_800,
+            },
+        }
+    }
+
+    #[test]
+    fn recent_swaps_excludes_principals_and_addresses() {
+        reset_state();
+        STATE.with(|s| {
+            s.borrow_mut()
+                .orders
+                .insert("order-0".into(), completed_order("order-0", 100));
+        });
+
+        let feed = get_recent_swaps_internal(10);
+        assert_eq!(feed.len(), 1);
+        // SwapSummary has no field for maker/destination_address/secret by construction,
+        // so this simply asserts the redacted fields that remain are the expected ones.
+        assert_eq!(feed[0].amount, 1_000);
+        assert_eq!(feed[0].completed_at, 100);
+    }
+
+    #[test]
+    fn recent_swaps_respects_limit_and_ordering() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state
+                .orders
+                .insert("order-0".into(), completed_order("order-0", 100));
+            state
+                .orders
+                .insert("order-1".into(), completed_order("order-1", 300));
+            state
+                .orders
+                .insert("order-2".into(), completed_order("order-2", 200));
+        });
+
+        let feed = get_recent_swaps_internal(2);
+        assert_eq!(feed.len(), 2);
+        assert_eq!(feed[0].completed_at, 300);
+        assert_eq!(feed[1].completed_at, 200);
+    }
+
+    #[test]
+    fn find_by_reference_matches_only_exact_reference() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let mut tagged = completed_order("order-0", 100);
+            tagged.client_reference = Some("invoice-42".into());
+            let mut untagged = completed_order("order-1", 100);
+            untagged.client_reference = None;
+            state.orders.insert("order-0".into(), tagged);
+            state.orders.insert("order-1".into(), untagged);
+        });
+
+        let found = find_orders_by_reference("invoice-42".into());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "order-0");
+    }
+
+    #[test]
+    fn bulk_statuses_mix_known_and_unknown_ids() {
+        reset_state();
+        STATE.with(|s| {
+            s.borrow_mut()
+                .orders
+                .insert("order-0".into(), completed_order("order-0", 100));
+        });
+
+        let result =
+            get_order_statuses(vec!["order-0".into(), "order-missing".into()]).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                ("order-0".into(), Some(SwapOrderStatus::Completed)),
+                ("order-missing".into(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn refundable_orders_excludes_unexpired_and_other_makers() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let expired = crate::orders::create_cross_chain_swap_order_internal(
+            maker,
+            crate::types::CreateOrderRequest {
+                src_chain: crate::types::Chain::ICP,
+                dst_chain: crate::types::Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap();
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&expired).unwrap().expires_at = 5);
+
+        let unexpired = crate::orders::create_cross_chain_swap_order_internal(
+            maker,
+            crate::types::CreateOrderRequest {
+                src_chain: crate::types::Chain::ICP,
+                dst_chain: crate::types::Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap();
+        STATE.with(|s| s.borrow_mut().orders.get_mut(&unexpired).unwrap().expires_at = 1_000);
+
+        let refundable = get_refundable_orders_internal(maker, 100);
+        assert_eq!(refundable.len(), 1);
+        assert_eq!(refundable[0].id, expired);
+    }
+
+    #[test]
+    fn canister_config_reflects_current_tunables() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.safety_deposit_bps = 250);
+        assert_eq!(get_canister_config().safety_deposit_bps, 250);
+    }
+
+    #[test]
+    fn order_detail_merges_order_and_events() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let order_id = crate::orders::create_cross_chain_swap_order_internal(
+            maker,
+            crate::types::CreateOrderRequest {
+                src_chain: crate::types::Chain::ICP,
+                dst_chain: crate::types::Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            5,
+        )
+        .unwrap();
+
+        let detail = get_order_detail(order_id.clone()).unwrap();
+        assert_eq!(detail.order.id, order_id);
+        assert_eq!(detail.events.len(), 1);
+        assert_eq!(detail.events[0].kind, "Created");
+        assert!(detail.htlcs.is_empty());
+    }
+
+    #[test]
+    fn order_detail_for_unknown_order_errors() {
+        reset_state();
+        assert!(get_order_detail("order-missing".into()).is_err());
+    }
+
+    #[test]
+    fn claimable_and_refundable_orders_are_surfaced_with_the_right_action() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            let mut claimable = completed_order("order-claimable", 0);
+            claimable.status = SwapOrderStatus::EscrowFunded;
+            claimable.expires_at = 1_000;
+            state.orders.insert("order-claimable".into(), claimable);
+
+            let mut refundable = completed_order("order-refundable", 0);
+            refundable.status = SwapOrderStatus::Created;
+            refundable.expires_at = 50;
+            state.orders.insert("order-refundable".into(), refundable);
+
+            let mut settled = completed_order("order-settled", 0);
+            settled.status = SwapOrderStatus::Completed;
+            settled.expires_at = 50;
+            state.orders.insert("order-settled".into(), settled);
+        });
+
+        let mut items = get_orders_needing_action_internal(100);
+        items.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+
+        assert_eq!(
+            items,
+            vec![
+                ActionItem { order_id: "order-claimable".into(), action: RecommendedAction::Claim },
+                ActionItem { order_id: "order-refundable".into(), action: RecommendedAction::Refund },
+            ]
+        );
+    }
+
+    #[test]
+    fn bulk_statuses_rejects_too_many_ids() {
+        reset_state();
+        let ids = (0..MAX_BULK_STATUS_IDS + 1)
+            .map(|i| format!("order-{i}"))
+            .collect();
+        assert_eq!(
+            get_order_statuses(ids),
+            Err(SwapError::TooManyIds {
+                max: MAX_BULK_STATUS_IDS,
+                provided: MAX_BULK_STATUS_IDS + 1,
+            })
+        );
+    }
+
+    #[test]
+    fn a_route_appears_for_each_ordered_pair_of_chains_in_a_pool() {
+        reset_state();
+        let pool_id = crate::pools::create_unified_liquidity_pool("USDC".into(), Chain::ICP);
+        crate::pools::add_chain_to_pool(pool_id, Chain::Ethereum).unwrap();
+
+        let routes = get_supported_routes_internal();
+
+        assert_eq!(routes.len(), 2);
+        assert!(routes.contains(&SwapRoute {
+            src_chain: Chain::ICP,
+            src_token: "USDC".into(),
+            dst_chain: Chain::Ethereum,
+            dst_token: "USDC".into(),
+        }));
+        assert!(routes.contains(&SwapRoute {
+            src_chain: Chain::Ethereum,
+            src_token: "USDC".into(),
+            dst_chain: Chain::ICP,
+            dst_token: "USDC".into(),
+        }));
+    }
+
+    #[test]
+    fn no_routes_exist_for_a_single_chain_pool() {
+        reset_state();
+        crate::pools::create_unified_liquidity_pool("USDC".into(), Chain::ICP);
+
+        assert!(get_supported_routes_internal().is_empty());
+    }
+
+    #[test]
+    fn a_route_disappears_once_its_token_is_paused() {
+        reset_state();
+        let pool_id = crate::pools::create_unified_liquidity_pool("USDC".into(), Chain::ICP);
+        crate::pools::add_chain_to_pool(pool_id, Chain::Ethereum).unwrap();
+        STATE.with(|s| s.borrow_mut().paused_tokens.insert("USDC".into(), true));
+
+        assert!(get_supported_routes_internal().is_empty());
+    }
+
+    #[test]
+    fn a_route_disappears_once_its_destination_chain_goes_unhealthy() {
+        reset_state();
+        let pool_id = crate::pools::create_unified_liquidity_pool("USDC".into(), Chain::ICP);
+        crate::pools::add_chain_to_pool(pool_id, Chain::Ethereum).unwrap();
+        crate::chains::set_chain_health_internal(Chain::Ethereum, false);
+
+        assert!(get_supported_routes_internal().is_empty());
+    }
+
+    fn open_order(id: &str, created_at: u64) -> SwapOrder {
+        let mut order = completed_order(id, created_at);
+        order.status = SwapOrderStatus::Created;
+        order.completed_at = None;
+        order
+    }
+
+    #[test]
+    fn the_snapshot_includes_every_open_order_with_sensitive_fields_removed() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.orders.insert("order-a".into(), open_order("order-a", 10));
+            state.orders.insert("order-b".into(), open_order("order-b", 20));
+        });
+
+        let snapshot = export_order_book_snapshot_internal(0, 500, 999);
+        assert_eq!(snapshot.timestamp, 999);
+        assert_eq!(snapshot.orders.len(), 2);
+        assert_eq!(snapshot.orders[0].id, "order-a");
+        assert_eq!(snapshot.orders[1].id, "order-b");
+        // PublicOrder has no field for maker/destination_address/secret by
+        // construction, so this simply asserts the fields that remain are correct.
+        assert_eq!(snapshot.orders[0].amount, 1_000);
+        assert_eq!(snapshot.orders[0].status, SwapOrderStatus::Created);
+    }
+
+    #[test]
+    fn the_snapshot_excludes_orders_in_a_terminal_status() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.orders.insert("order-open".into(), open_order("order-open", 10));
+            state.orders.insert("order-done".into(), completed_order("order-done", 20));
+        });
+
+        let snapshot = export_order_book_snapshot_internal(0, 500, 0);
+        assert_eq!(snapshot.orders.len(), 1);
+        assert_eq!(snapshot.orders[0].id, "order-open");
+    }
+
+    #[test]
+    fn the_snapshot_is_paginated_in_stable_id_order() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            for i in 0..5 {
+                let id = format!("order-{i}");
+                state.orders.insert(id.clone(), open_order(&id, i));
+            }
+        });
+
+        let page = export_order_book_snapshot_internal(2, 2, 0);
+        let ids: Vec<String> = page.orders.iter().map(|o| o.id.clone()).collect();
+        assert_eq!(ids, vec!["order-2".to_string(), "order-3".to_string()]);
+    }
+
+    #[test]
+    fn the_snapshot_page_size_is_capped_at_the_maximum() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            for i in 0..3 {
+                let id = format!("order-{i}");
+                state.orders.insert(id.clone(), open_order(&id, i));
+            }
+        });
+
+        let page = export_order_book_snapshot_internal(0, (MAX_SNAPSHOT_PAGE_SIZE + 100) as u64, 0);
+        assert_eq!(page.orders.len(), 3);
+    }
+
+    #[test]
+    fn listing_orders_on_an_empty_store_returns_an_empty_page_and_zero_total() {
+        reset_state();
+        let (page, total) = list_orders_paged_internal(0, 10).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn listing_orders_returns_a_partial_last_page() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            for i in 0..5 {
+                let id = format!("order-{i}");
+                state.orders.insert(id.clone(), open_order(&id, i));
+            }
+        });
+
+        let (page, total) = list_orders_paged_internal(3, 10).unwrap();
+        let ids: Vec<String> = page.iter().map(|o| o.id.clone()).collect();
+        assert_eq!(ids, vec!["order-3".to_string(), "order-4".to_string()]);
+        assert_eq!(total, 5);
+    }
+
+    #[test]
+    fn listing_orders_with_an_offset_past_the_end_returns_an_empty_page_but_the_real_total() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            for i in 0..3 {
+                let id = format!("order-{i}");
+                state.orders.insert(id.clone(), open_order(&id, i));
+            }
+        });
+
+        let (page, total) = list_orders_paged_internal(100, 10).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn listing_orders_sorts_by_created_at_then_id() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.orders.insert("order-b".into(), open_order("order-b", 5));
+            state.orders.insert("order-a".into(), open_order("order-a", 5));
+            state.orders.insert("order-c".into(), open_order("order-c", 1));
+        });
+
+        let (page, _total) = list_orders_paged_internal(0, 10).unwrap();
+        let ids: Vec<String> = page.iter().map(|o| o.id.clone()).collect();
+        // order-c's created_at (1) sorts before the tied pair, which then
+        // breaks the tie by id.
+        assert_eq!(ids, vec!["order-c".to_string(), "order-a".to_string(), "order-b".to_string()]);
+    }
+
+    #[test]
+    fn listing_orders_rejects_a_limit_above_the_page_cap() {
+        reset_state();
+        let result = list_orders_paged_internal(0, MAX_PAGE_SIZE + 1);
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn listing_htlcs_on_an_empty_store_returns_an_empty_page_and_zero_total() {
+        reset_state();
+        let (page, total) = list_htlcs_paged_internal(0, 10).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn listing_htlcs_flattens_every_order_and_returns_a_partial_last_page() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let mut order_ids = Vec::new();
+        for i in 0..3u64 {
+            let id = crate::orders::create_cross_chain_swap_order_internal(
+                maker,
+                crate::types::CreateOrderRequest {
+                    src_chain: Chain::ICP,
+                    dst_chain: Chain::Ethereum,
+                    src_token: "ICP".into(),
+                    dst_token: "ETH".into(),
+                    amount: 10_000,
+                    destination_address: "0xdead".into(),
+                    escrowed_safety_deposit: 100,
+                    client_reference: None,
+                    destinations: None,
+                    auto_refund_after: None,
+                    timelocks: None,
+                },
+                i,
+            )
+            .unwrap();
+            crate::htlc::create_htlc_escrow(&id, vec![1u8; crate::htlc::HASHLOCK_LEN_BYTES]).unwrap();
+            order_ids.push(id);
+        }
+
+        let (page, total) = list_htlcs_paged_internal(1, 10).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].order_id, order_ids[1]);
+        assert_eq!(page[1].order_id, order_ids[2]);
+    }
+
+    #[test]
+    fn listing_htlcs_with_an_offset_past_the_end_returns_an_empty_page_but_the_real_total() {
+        reset_state();
+        let maker = candid::Principal::anonymous();
+        let id = crate::orders::create_cross_chain_swap_order_internal(
+            maker,
+            crate::types::CreateOrderRequest {
+                src_chain: Chain::ICP,
+                dst_chain: Chain::Ethereum,
+                src_token: "ICP".into(),
+                dst_token: "ETH".into(),
+                amount: 10_000,
+                destination_address: "0xdead".into(),
+                escrowed_safety_deposit: 100,
+                client_reference: None,
+                destinations: None,
+                auto_refund_after: None,
+                timelocks: None,
+            },
+            0,
+        )
+        .unwrap();
+        crate::htlc::create_htlc_escrow(&id, vec![1u8; crate::htlc::HASHLOCK_LEN_BYTES]).unwrap();
+
+        let (page, total) = list_htlcs_paged_internal(100, 10).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(total, 1);
+    }
+
+    #[test]
+    fn listing_htlcs_rejects_a_limit_above_the_page_cap() {
+        reset_state();
+        let result = list_htlcs_paged_internal(0, MAX_PAGE_SIZE + 1);
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+}
@@ -0,0 +1,111 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::types::Chain;
+
+/// The native gas/fee token completion costs on `chain` are denominated in.
+pub fn gas_token(chain: Chain) -> &'static str {
+    match chain {
+        Chain::ICP => "cycles",
+        Chain::Ethereum | Chain::Base | Chain::Arbitrum => "ETH",
+        Chain::Solana => "SOL",
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeeEstimate {
+    pub token: String,
+    pub amount: f64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SwapQuote {
+    pub src_chain: Chain,
+    pub dst_chain: Chain,
+    pub amount: u128,
+    pub estimated_completion_fee: FeeEstimate,
+}
+
+/// Per-chain completion fee oracle, behind a trait so quoting can be unit
+/// tested without a live RPC/cycle-cost lookup.
+pub trait FeeOracle {
+    fn estimate_completion_fee(&self, chain: Chain) -> f64;
+}
+
+struct LiveFeeOracle;
+
+impl FeeOracle for LiveFeeOracle {
+    fn estimate_completion_fee(&self, chain: Chain) -> f64 {
+        match chain {
+            Chain::ICP => 0.0,
+            Chain::Ethereum | Chain::Base | Chain::Arbitrum => 0.002,
+            Chain::Solana => 0.000005,
+        }
+    }
+}
+
+/// Quotes a swap's completion cost in the destination chain's native gas
+/// token, so a taker claiming on Ethereum sees a fee in ETH and one claiming
+/// on Solana sees a fee in SOL instead of an ambiguous unitless number.
+pub fn quote_swap_with(
+    src_chain: Chain,
+    dst_chain: Chain,
+    amount: u128,
+    oracle: &impl FeeOracle,
+) -> SwapQuote {
+    SwapQuote {
+        src_chain,
+        dst_chain,
+        amount,
+        estimated_completion_fee: FeeEstimate {
+            token: gas_token(dst_chain).to_string(),
+            amount: oracle.estimate_completion_fee(dst_chain),
+        },
+    }
+}
+
+#[ic_cdk::query]
+pub fn quote_swap(src_chain: Chain, dst_chain: Chain, amount: u128) -> SwapQuote {
+    quote_swap_with(src_chain, dst_chain, amount, &LiveFeeOracle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockOracle {
+        fee: f64,
+    }
+
+    impl FeeOracle for MockOracle {
+        fn estimate_completion_fee(&self, _chain: Chain) -> f64 {
+            self.fee
+        }
+    }
+
+    #[test]
+    fn evm_destination_quotes_express_fees_in_eth() {
+        let quote = quote_swap_with(Chain::ICP, Chain::Ethereum, 10_000, &MockOracle { fee: 0.002 });
+        assert_eq!(quote.estimated_completion_fee.token, "ETH");
+        assert_eq!(quote.estimated_completion_fee.amount, 0.002);
+    }
+
+    #[test]
+    fn base_and_arbitrum_also_quote_in_eth() {
+        assert_eq!(gas_token(Chain::Base), "ETH");
+        assert_eq!(gas_token(Chain::Arbitrum), "ETH");
+    }
+
+    #[test]
+    fn solana_destination_quotes_express_fees_in_sol() {
+        let quote = quote_swap_with(Chain::ICP, Chain::Solana, 10_000, &MockOracle { fee: 0.000005 });
+        assert_eq!(quote.estimated_completion_fee.token, "SOL");
+        assert_eq!(quote.estimated_completion_fee.amount, 0.000005);
+    }
+
+    #[test]
+    fn icp_destination_quotes_in_cycles() {
+        let quote = quote_swap_with(Chain::Solana, Chain::ICP, 10_000, &MockOracle { fee: 0.0 });
+        assert_eq!(quote.estimated_completion_fee.token, "cycles");
+    }
+}
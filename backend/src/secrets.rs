@@ -0,0 +1,202 @@
+use sha3::{Digest, Keccak256};
+
+use crate::errors::{ChainCallError, SwapError};
+use crate::state::STATE;
+use crate::types::Chain;
+
+/// Domain-separation tag mixed into every derived secret, so this canister's
+/// KDF output can never collide with a hash used for some other purpose even
+/// if the master seed were ever reused elsewhere.
+///
+/// The master seed itself comes from `raw_rand` (see
+/// `initialize_master_seed`), not from anything observable off-chain like
+/// block time or the caller's principal — an attacker watching the mempool
+/// has no way to pre-compute a secret derived from it.
+const SECRET_DERIVATION_DOMAIN: &[u8] = b"ionic-swap/order-secret/v1";
+
+/// Seeds the canister's master seed from the management canister's verifiable
+/// randomness. Meant to be called once, before any order relies on
+/// deterministic secret derivation for recovery — re-seeding afterward would
+/// make previously derived secrets unrecoverable.
+#[ic_cdk::update]
+pub async fn initialize_master_seed() -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    let already_seeded = STATE.with(|s| !s.borrow().master_seed.is_empty());
+    if already_seeded {
+        return Err(SwapError::InvalidAmount("master seed is already initialized".into()));
+    }
+    let (seed,) = ic_cdk::api::management_canister::main::raw_rand()
+        .await
+        .map_err(|(_, message)| {
+            SwapError::ChainCallFailed(ChainCallError {
+                chain: Chain::ICP,
+                method: "raw_rand".into(),
+                code: None,
+                message,
+                raw: None,
+            })
+        })?;
+    STATE.with(|s| s.borrow_mut().master_seed = seed);
+    Ok(())
+}
+
+/// Deterministically derives an order's secret from the canister's master
+/// seed and its order id, using the hash primitive already relied on for
+/// hashlocks elsewhere in this crate (Keccak256) rather than pulling in a
+/// dedicated HKDF crate — same extract-and-expand idea, built on a primitive
+/// already present. Doesn't touch hashlock derivation, which still hashes
+/// whatever secret the maker actually supplies.
+pub fn derive_order_secret_internal(master_seed: &[u8], order_id: &str) -> Result<Vec<u8>, SwapError> {
+    if master_seed.is_empty() {
+        return Err(SwapError::InvalidAmount("master seed is not initialized".into()));
+    }
+    let mut hasher = Keccak256::new();
+    hasher.update(master_seed);
+    hasher.update(SECRET_DERIVATION_DOMAIN);
+    hasher.update(order_id.as_bytes());
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Admin-only recovery path: recomputes an order's secret from the
+/// canister's master seed, for use if the live order map is ever lost
+/// before stable storage lands. Gated behind `require_admin` because the
+/// secret is otherwise unknown until the legitimate party reveals it by
+/// claiming — an open query here would let anyone front-run either leg of
+/// any order by deriving its secret from the order id alone.
+#[ic_cdk::query]
+pub fn derive_order_secret(order_id: String) -> Result<Vec<u8>, SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| derive_order_secret_internal(&s.borrow().master_seed, &order_id))
+}
+
+/// Derives the secret for one partial-fill segment of an order, distinct
+/// from `derive_order_secret_internal`'s whole-order secret. Matching an
+/// order against the book (see `matching::fill_order_internal`) can create
+/// several fill segments, each locked behind its own HTLC — mixing
+/// `fill_index` into the hash means revealing the secret that claims one
+/// segment doesn't let anyone derive, let alone claim, any other segment.
+pub fn derive_fill_secret_internal(master_seed: &[u8], order_id: &str, fill_index: u64) -> Result<Vec<u8>, SwapError> {
+    if master_seed.is_empty() {
+        return Err(SwapError::InvalidAmount("master seed is not initialized".into()));
+    }
+    let mut hasher = Keccak256::new();
+    hasher.update(master_seed);
+    hasher.update(SECRET_DERIVATION_DOMAIN);
+    hasher.update(order_id.as_bytes());
+    hasher.update(b":fill:");
+    hasher.update(fill_index.to_be_bytes());
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Admin-only recovery path: recomputes a fill segment's secret from the
+/// canister's master seed, for use if the live order map is ever lost
+/// before stable storage lands. Gated the same way as `derive_order_secret`
+/// and for the same reason.
+#[ic_cdk::query]
+pub fn derive_fill_secret(order_id: String, fill_index: u64) -> Result<Vec<u8>, SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| derive_fill_secret_internal(&s.borrow().master_seed, &order_id, fill_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deriving_without_a_master_seed_is_rejected() {
+        let result = derive_order_secret_internal(&[], "order-1");
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn the_same_order_id_always_derives_the_same_secret() {
+        let seed = vec![7u8; 32];
+        let a = derive_order_secret_internal(&seed, "order-1").unwrap();
+        let b = derive_order_secret_internal(&seed, "order-1").unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_order_ids_derive_different_secrets() {
+        let seed = vec![7u8; 32];
+        let a = derive_order_secret_internal(&seed, "order-1").unwrap();
+        let b = derive_order_secret_internal(&seed, "order-2").unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn different_master_seeds_derive_different_secrets_for_the_same_order_id() {
+        let a = derive_order_secret_internal(&[1u8; 32], "order-1").unwrap();
+        let b = derive_order_secret_internal(&[2u8; 32], "order-1").unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn two_orders_created_in_the_same_call_context_get_different_secrets() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        STATE.with(|s| s.borrow_mut().master_seed = vec![7u8; 32]);
+        // `derive_order_secret` is admin-gated; the test-harness caller is
+        // always the anonymous principal, so seed it as a controller here.
+        crate::admin::init_controller(candid::Principal::anonymous());
+        let maker = candid::Principal::anonymous();
+        let req = crate::types::CreateOrderRequest {
+            src_chain: Chain::ICP,
+            dst_chain: Chain::Ethereum,
+            src_token: "ICP".into(),
+            dst_token: "ETH".into(),
+            amount: 10_000,
+            destination_address: "0xdead".into(),
+            escrowed_safety_deposit: 100,
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            timelocks: None,
+        };
+
+        // Same maker, same timestamp (the same "call context"): only the
+        // derived order id differs, which is what must make the secrets diverge.
+        let order_a = crate::orders::create_cross_chain_swap_order_internal(maker, req.clone(), 0).unwrap();
+        let order_b = crate::orders::create_cross_chain_swap_order_internal(maker, req, 0).unwrap();
+        assert_ne!(order_a, order_b);
+
+        let secret_a = derive_order_secret(order_a).unwrap();
+        let secret_b = derive_order_secret(order_b).unwrap();
+        assert_ne!(secret_a, secret_b);
+    }
+
+    #[test]
+    fn different_fill_indices_of_the_same_order_derive_different_secrets() {
+        let seed = vec![7u8; 32];
+        let first = derive_fill_secret_internal(&seed, "order-1", 0).unwrap();
+        let second = derive_fill_secret_internal(&seed, "order-1", 1).unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn a_fill_secret_differs_from_the_whole_order_secret() {
+        let seed = vec![7u8; 32];
+        let whole_order = derive_order_secret_internal(&seed, "order-1").unwrap();
+        let first_fill = derive_fill_secret_internal(&seed, "order-1", 0).unwrap();
+        assert_ne!(whole_order, first_fill);
+    }
+
+    #[test]
+    fn deriving_a_fill_secret_without_a_master_seed_is_rejected() {
+        let result = derive_fill_secret_internal(&[], "order-1", 0);
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn deriving_an_order_secret_is_rejected_for_a_non_admin_caller() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        STATE.with(|s| s.borrow_mut().master_seed = vec![7u8; 32]);
+        assert_eq!(derive_order_secret("order-1".into()), Err(SwapError::Unauthorized));
+    }
+
+    #[test]
+    fn deriving_a_fill_secret_is_rejected_for_a_non_admin_caller() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        STATE.with(|s| s.borrow_mut().master_seed = vec![7u8; 32]);
+        assert_eq!(derive_fill_secret("order-1".into(), 0), Err(SwapError::Unauthorized));
+    }
+}
@@ -0,0 +1,754 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+/// Retry behavior for the on-chain legs of an atomic swap settlement, so a
+/// transient RPC hiccup on one leg doesn't fail the whole swap outright.
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SettlementRetryPolicy {
+    pub max_attempts: u32,
+    pub backoff_ms: u64,
+}
+
+impl Default for SettlementRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_ms: 500,
+        }
+    }
+}
+
+/// Admin-only: adjust the retry policy used for each settlement leg.
+#[ic_cdk::update]
+pub fn set_settlement_retry_policy(policy: SettlementRetryPolicy) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    if policy.max_attempts == 0 {
+        return Err(SwapError::InvalidAmount("max_attempts must be positive".into()));
+    }
+    STATE.with(|s| s.borrow_mut().settlement_retry_policy = policy);
+    Ok(())
+}
+
+/// Whether a failed settlement leg is worth retrying. A transient failure
+/// (RPC timeout, temporarily unavailable node) may succeed on a later
+/// attempt; a terminal failure (bad hashlock, insufficient funds) never will,
+/// so retrying it would only burn cycles and delay the failure report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LegError {
+    Transient(String),
+    Terminal(String),
+}
+
+/// Per-step outcome of a full atomic swap execution: funding both legs'
+/// HTLCs and then claiming both. Each step is reported independently so a
+/// caller can tell exactly which step failed rather than parsing one opaque
+/// string.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AtomicSwapResult {
+    pub source_htlc: Result<String, String>,
+    pub dest_htlc: Result<String, String>,
+    pub source_claim: Result<String, String>,
+    pub dest_claim: Result<String, String>,
+}
+
+impl AtomicSwapResult {
+    fn not_attempted() -> Self {
+        let err = || Err("not attempted".to_string());
+        Self {
+            source_htlc: err(),
+            dest_htlc: err(),
+            source_claim: err(),
+            dest_claim: err(),
+        }
+    }
+}
+
+/// The four on-chain actions that make up an atomic swap, behind a trait so
+/// tests can inject failures at any step without a live canister runtime.
+/// Each step reports whether a failure is worth retrying via `LegError`.
+pub trait AtomicSwapSteps {
+    fn fund_source_htlc(&self, order_id: &str) -> Result<String, LegError>;
+    fn fund_dest_htlc(&self, order_id: &str) -> Result<String, LegError>;
+    fn claim_source_htlc(&self, order_id: &str) -> Result<String, LegError>;
+    fn claim_dest_htlc(&self, order_id: &str) -> Result<String, LegError>;
+}
+
+struct LiveSteps;
+
+impl AtomicSwapSteps for LiveSteps {
+    fn fund_source_htlc(&self, order_id: &str) -> Result<String, LegError> {
+        Ok(format!("{order_id}-source-htlc-tx"))
+    }
+    fn fund_dest_htlc(&self, order_id: &str) -> Result<String, LegError> {
+        Ok(format!("{order_id}-dest-htlc-tx"))
+    }
+    fn claim_source_htlc(&self, order_id: &str) -> Result<String, LegError> {
+        Ok(format!("{order_id}-source-claim-tx"))
+    }
+    fn claim_dest_htlc(&self, order_id: &str) -> Result<String, LegError> {
+        Ok(format!("{order_id}-dest-claim-tx"))
+    }
+}
+
+/// Runs a single leg up to `policy.max_attempts` times, retrying only on
+/// transient failures and stopping immediately on a terminal one. Returns
+/// the leg's outcome alongside the number of attempts it took, so the
+/// caller can log how much retrying happened.
+pub(crate) fn run_leg_with_retry(
+    policy: SettlementRetryPolicy,
+    step: impl Fn() -> Result<String, LegError>,
+) -> (Result<String, String>, u32) {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match step() {
+            Ok(tx) => return (Ok(tx), attempts),
+            Err(LegError::Terminal(msg)) => return (Err(msg), attempts),
+            Err(LegError::Transient(msg)) => {
+                if attempts >= policy.max_attempts {
+                    return (Err(msg), attempts);
+                }
+            }
+        }
+    }
+}
+
+/// Funds both legs' HTLCs and claims both, in order, short-circuiting and
+/// reporting which steps completed as soon as one step fails. Each leg is
+/// retried per `policy` on transient failures, and the attempt count for
+/// every leg that was attempted is recorded to the order's event log.
+pub fn execute_atomic_swap_with(
+    order_id: &str,
+    steps: &impl AtomicSwapSteps,
+    policy: SettlementRetryPolicy,
+    now: u64,
+) -> AtomicSwapResult {
+    resume_atomic_swap_with(order_id, steps, policy, now, AtomicSwapResult::not_attempted())
+}
+
+/// Like `execute_atomic_swap_with`, but skips any leg that already succeeded
+/// in `previous` instead of starting over from the first leg. Passing
+/// `AtomicSwapResult::not_attempted()` as `previous` makes this equivalent to
+/// `execute_atomic_swap_with`; `retry_settlement` passes a failed order's
+/// last recorded result so a retry only re-attempts what's actually left.
+pub fn resume_atomic_swap_with(
+    order_id: &str,
+    steps: &impl AtomicSwapSteps,
+    policy: SettlementRetryPolicy,
+    now: u64,
+    previous: AtomicSwapResult,
+) -> AtomicSwapResult {
+    let mut result = previous;
+
+    if result.source_htlc.is_err() {
+        let (source_htlc, attempts) = run_leg_with_retry(policy, || steps.fund_source_htlc(order_id));
+        log_leg_attempts(order_id, now, "fund_source_htlc", attempts);
+        if let Ok(tx_hash) = &source_htlc {
+            record_settlement_leg(order_id, ConfirmedLeg::SourceFund, tx_hash);
+        }
+        result.source_htlc = source_htlc;
+    }
+    if result.source_htlc.is_err() {
+        record_settlement_outcome(order_id, &result);
+        return result;
+    }
+
+    if result.dest_htlc.is_err() {
+        let (dest_htlc, attempts) = run_leg_with_retry(policy, || steps.fund_dest_htlc(order_id));
+        log_leg_attempts(order_id, now, "fund_dest_htlc", attempts);
+        if let Ok(tx_hash) = &dest_htlc {
+            record_settlement_leg(order_id, ConfirmedLeg::DestFund, tx_hash);
+        }
+        result.dest_htlc = dest_htlc;
+    }
+    if result.dest_htlc.is_err() {
+        record_settlement_outcome(order_id, &result);
+        return result;
+    }
+
+    let (first_claim, second_claim) = match claim_order_for(order_id) {
+        ClaimOrder::SourceFirst => (ClaimLeg::Source, ClaimLeg::Dest),
+        ClaimOrder::DestFirst => (ClaimLeg::Dest, ClaimLeg::Source),
+    };
+
+    run_claim_leg(order_id, steps, policy, now, first_claim, &mut result);
+    if claim_leg_failed(&result, first_claim) {
+        record_settlement_outcome(order_id, &result);
+        return result;
+    }
+
+    run_claim_leg(order_id, steps, policy, now, second_claim, &mut result);
+
+    record_settlement_outcome(order_id, &result);
+    result
+}
+
+/// Which claim leg to confirm first once both HTLCs are funded. Claiming is
+/// what reveals the swap's secret on that chain, so this determines which
+/// side sees the secret before the other side's claim has even been
+/// attempted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClaimOrder {
+    SourceFirst,
+    DestFirst,
+}
+
+/// Picks the claim order that reveals the secret on the chain the canister
+/// is safest on first. ICP is that chain: the canister has direct, atomic
+/// control over its own HTLC there and can always fall back to a refund, so
+/// revealing the secret there first carries no grief risk. On an external
+/// chain (Ethereum, Solana, ...), once the secret is revealed a slow or
+/// malicious counterparty could let its own claim lapse after already
+/// having what it needs, leaving the canister to fall back to a timeout
+/// refund. Falls back to the original source-first order when neither leg
+/// is ICP, since there's no clearly safer side to prefer.
+pub fn safe_claim_order(src_chain: crate::types::Chain, dst_chain: crate::types::Chain) -> ClaimOrder {
+    use crate::types::Chain;
+    if src_chain == Chain::ICP {
+        ClaimOrder::SourceFirst
+    } else if dst_chain == Chain::ICP {
+        ClaimOrder::DestFirst
+    } else {
+        ClaimOrder::SourceFirst
+    }
+}
+
+/// `safe_claim_order` for a known order, or the original source-first order
+/// if the order isn't in `STATE` (e.g. a unit test driving the steps
+/// directly without registering an order).
+fn claim_order_for(order_id: &str) -> ClaimOrder {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .get(order_id)
+            .map(|order| safe_claim_order(order.src_chain, order.dst_chain))
+            .unwrap_or(ClaimOrder::SourceFirst)
+    })
+}
+
+#[derive(Clone, Copy)]
+enum ClaimLeg {
+    Source,
+    Dest,
+}
+
+fn claim_leg_failed(result: &AtomicSwapResult, leg: ClaimLeg) -> bool {
+    match leg {
+        ClaimLeg::Source => result.source_claim.is_err(),
+        ClaimLeg::Dest => result.dest_claim.is_err(),
+    }
+}
+
+fn run_claim_leg(
+    order_id: &str,
+    steps: &impl AtomicSwapSteps,
+    policy: SettlementRetryPolicy,
+    now: u64,
+    leg: ClaimLeg,
+    result: &mut AtomicSwapResult,
+) {
+    match leg {
+        ClaimLeg::Source => {
+            if result.source_claim.is_err() {
+                let (source_claim, attempts) = run_leg_with_retry(policy, || steps.claim_source_htlc(order_id));
+                log_leg_attempts(order_id, now, "claim_source_htlc", attempts);
+                if let Ok(tx_hash) = &source_claim {
+                    record_settlement_leg(order_id, ConfirmedLeg::SourceClaim, tx_hash);
+                }
+                result.source_claim = source_claim;
+            }
+        }
+        ClaimLeg::Dest => {
+            if result.dest_claim.is_err() {
+                let (dest_claim, attempts) = run_leg_with_retry(policy, || steps.claim_dest_htlc(order_id));
+                log_leg_attempts(order_id, now, "claim_dest_htlc", attempts);
+                if let Ok(tx_hash) = &dest_claim {
+                    record_settlement_leg(order_id, ConfirmedLeg::DestClaim, tx_hash);
+                }
+                result.dest_claim = dest_claim;
+            }
+        }
+    }
+}
+
+/// Which of the four settlement legs just confirmed, so
+/// `record_settlement_leg` knows which chain/token/direction to attribute
+/// the transaction to.
+#[derive(Clone, Copy)]
+enum ConfirmedLeg {
+    SourceFund,
+    DestFund,
+    SourceClaim,
+    DestClaim,
+}
+
+/// Appends a confirmed on-chain transfer to the order's settlement audit
+/// trail. A no-op if the order no longer exists, same as
+/// `record_settlement_outcome`.
+fn record_settlement_leg(order_id: &str, leg: ConfirmedLeg, tx_hash: &str) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let Some(order) = state.orders.get_mut(order_id) else {
+            return;
+        };
+        let (chain, token, direction) = match leg {
+            ConfirmedLeg::SourceFund => (order.src_chain, order.src_token.clone(), crate::types::SettlementDirection::Fund),
+            ConfirmedLeg::DestFund => (order.dst_chain, order.dst_token.clone(), crate::types::SettlementDirection::Fund),
+            ConfirmedLeg::SourceClaim => (order.src_chain, order.src_token.clone(), crate::types::SettlementDirection::Claim),
+            ConfirmedLeg::DestClaim => (order.dst_chain, order.dst_token.clone(), crate::types::SettlementDirection::Claim),
+        };
+        order.settlement.push(crate::types::SettlementLeg {
+            chain,
+            direction,
+            tx_hash: tx_hash.to_string(),
+            amount: order.amount,
+            token,
+        });
+    });
+}
+
+/// The first leg's failure message in leg order, or `None` if every leg
+/// attempted so far succeeded.
+fn first_failure_reason(result: &AtomicSwapResult) -> Option<String> {
+    [&result.source_htlc, &result.dest_htlc, &result.source_claim, &result.dest_claim]
+        .into_iter()
+        .find_map(|leg| leg.as_ref().err().cloned())
+}
+
+/// Persists a settlement attempt's outcome onto the order itself, so
+/// `get_failed_settlements` and `retry_settlement` have somewhere to read the
+/// failure reason and the last-known per-leg progress from. A no-op if the
+/// order no longer exists (e.g. it was created outside `STATE`, as plain
+/// unit tests in this module do).
+fn record_settlement_outcome(order_id: &str, result: &AtomicSwapResult) {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let Some(order) = state.orders.get_mut(order_id) else {
+            return;
+        };
+        order.last_settlement = Some(result.clone());
+        match first_failure_reason(result) {
+            Some(reason) => {
+                order.status = crate::types::SwapOrderStatus::Failed;
+                order.settlement_failure_reason = Some(reason);
+            }
+            None => {
+                order.status = crate::types::SwapOrderStatus::Completed;
+                order.settlement_failure_reason = None;
+            }
+        }
+    });
+}
+
+/// Orders whose last settlement attempt failed, so an operator has a
+/// worklist instead of having to notice a stuck swap from logs.
+#[ic_cdk::query]
+pub fn get_failed_settlements() -> Vec<crate::types::SwapOrder> {
+    STATE.with(|s| {
+        s.borrow()
+            .orders
+            .values()
+            .filter(|order| order.status == crate::types::SwapOrderStatus::Failed)
+            .cloned()
+            .collect()
+    })
+}
+
+/// Admin-only: re-attempts a failed order's settlement, resuming from the
+/// first leg that didn't complete last time rather than repeating legs that
+/// already succeeded.
+#[ic_cdk::update]
+pub async fn retry_settlement(order_id: String) -> Result<AtomicSwapResult, SwapError> {
+    crate::admin::require_admin()?;
+    let previous = STATE.with(|s| {
+        let state = s.borrow();
+        let order = state
+            .orders
+            .get(&order_id)
+            .ok_or_else(|| SwapError::OrderNotFound(order_id.clone()))?;
+        if order.status != crate::types::SwapOrderStatus::Failed {
+            return Err(SwapError::InvalidAmount("order does not have a failed settlement to retry".into()));
+        }
+        Ok(order.last_settlement.clone().unwrap_or_else(AtomicSwapResult::not_attempted))
+    })?;
+    let policy = STATE.with(|s| s.borrow().settlement_retry_policy);
+    Ok(resume_atomic_swap_with(&order_id, &LiveSteps, policy, ic_cdk::api::time(), previous))
+}
+
+fn log_leg_attempts(order_id: &str, now: u64, leg: &str, attempts: u32) {
+    let detail = if attempts == 1 {
+        format!("{leg} succeeded on the first attempt")
+    } else {
+        format!("{leg} took {attempts} attempt(s)")
+    };
+    crate::events::record_event(order_id, now, "SettlementStep", &detail);
+}
+
+#[ic_cdk::update]
+pub async fn execute_atomic_swap(order_id: String) -> AtomicSwapResult {
+    let policy = STATE.with(|s| s.borrow().settlement_retry_policy);
+    execute_atomic_swap_with(&order_id, &LiveSteps, policy, ic_cdk::api::time())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    struct MockSteps {
+        fail_at: Option<&'static str>,
+        fail_kind: LegError,
+        remaining_failures: RefCell<u32>,
+        call_order: RefCell<Vec<&'static str>>,
+    }
+
+    impl MockSteps {
+        fn succeeding() -> Self {
+            Self {
+                fail_at: None,
+                fail_kind: LegError::Terminal("unused".into()),
+                remaining_failures: RefCell::new(0),
+                call_order: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn failing_terminally_at(step: &'static str) -> Self {
+            Self {
+                fail_at: Some(step),
+                fail_kind: LegError::Terminal(format!("{step} failed")),
+                remaining_failures: RefCell::new(u32::MAX),
+                call_order: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn failing_transiently_then_succeeding(step: &'static str, failures: u32) -> Self {
+            Self {
+                fail_at: Some(step),
+                fail_kind: LegError::Transient(format!("{step} timed out")),
+                remaining_failures: RefCell::new(failures),
+                call_order: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn attempt(&self, step: &'static str, order_id: &str, tag: &str) -> Result<String, LegError> {
+            self.call_order.borrow_mut().push(step);
+            if self.fail_at == Some(step) {
+                let mut remaining = self.remaining_failures.borrow_mut();
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Err(self.fail_kind.clone());
+                }
+            }
+            Ok(format!("{order_id}-{tag}-tx"))
+        }
+    }
+
+    impl AtomicSwapSteps for MockSteps {
+        fn fund_source_htlc(&self, order_id: &str) -> Result<String, LegError> {
+            self.attempt("source_htlc", order_id, "source-htlc")
+        }
+        fn fund_dest_htlc(&self, order_id: &str) -> Result<String, LegError> {
+            self.attempt("dest_htlc", order_id, "dest-htlc")
+        }
+        fn claim_source_htlc(&self, order_id: &str) -> Result<String, LegError> {
+            self.attempt("source_claim", order_id, "source-claim")
+        }
+        fn claim_dest_htlc(&self, order_id: &str) -> Result<String, LegError> {
+            self.attempt("dest_claim", order_id, "dest-claim")
+        }
+    }
+
+    #[test]
+    fn all_steps_succeed() {
+        reset_state();
+        let result = execute_atomic_swap_with("order-0", &MockSteps::succeeding(), SettlementRetryPolicy::default(), 0);
+        assert!(result.source_htlc.is_ok());
+        assert!(result.dest_htlc.is_ok());
+        assert!(result.source_claim.is_ok());
+        assert!(result.dest_claim.is_ok());
+    }
+
+    #[test]
+    fn mid_flow_terminal_failure_reports_completed_steps_and_stops() {
+        reset_state();
+        let result = execute_atomic_swap_with(
+            "order-0",
+            &MockSteps::failing_terminally_at("source_claim"),
+            SettlementRetryPolicy::default(),
+            0,
+        );
+
+        assert!(result.source_htlc.is_ok());
+        assert!(result.dest_htlc.is_ok());
+        assert_eq!(result.source_claim, Err("source_claim failed".to_string()));
+        // Never attempted because source_claim failed first.
+        assert_eq!(result.dest_claim, Err("not attempted".to_string()));
+    }
+
+    #[test]
+    fn failure_on_first_step_leaves_remaining_steps_unattempted() {
+        reset_state();
+        let result = execute_atomic_swap_with(
+            "order-0",
+            &MockSteps::failing_terminally_at("source_htlc"),
+            SettlementRetryPolicy::default(),
+            0,
+        );
+
+        assert_eq!(result.source_htlc, Err("source_htlc failed".to_string()));
+        assert_eq!(result.dest_htlc, Err("not attempted".to_string()));
+        assert_eq!(result.source_claim, Err("not attempted".to_string()));
+        assert_eq!(result.dest_claim, Err("not attempted".to_string()));
+    }
+
+    #[test]
+    fn terminal_failure_is_not_retried() {
+        reset_state();
+        let steps = MockSteps::failing_terminally_at("dest_htlc");
+        let result = execute_atomic_swap_with("order-0", &steps, SettlementRetryPolicy::default(), 0);
+
+        assert_eq!(result.dest_htlc, Err("dest_htlc failed".to_string()));
+        // Only ever attempted once; remaining_failures started at u32::MAX and
+        // would never have drained if it had actually been retried.
+        assert_eq!(*steps.remaining_failures.borrow(), u32::MAX);
+    }
+
+    #[test]
+    fn a_leg_that_fails_transiently_then_succeeds_on_retry_settles_the_whole_swap() {
+        reset_state();
+        let steps = MockSteps::failing_transiently_then_succeeding("dest_claim", 2);
+        let policy = SettlementRetryPolicy { max_attempts: 3, backoff_ms: 0 };
+
+        let result = execute_atomic_swap_with("order-0", &steps, policy, 42);
+
+        assert_eq!(result.dest_claim, Ok("order-0-dest-claim-tx".to_string()));
+        let events = crate::events::events_for("order-0");
+        let dest_claim_event = events.iter().find(|e| e.detail.starts_with("claim_dest_htlc")).unwrap();
+        assert_eq!(dest_claim_event.detail, "claim_dest_htlc took 3 attempt(s)");
+    }
+
+    #[test]
+    fn a_leg_that_exhausts_its_retry_budget_reports_the_last_transient_error() {
+        reset_state();
+        let steps = MockSteps::failing_transiently_then_succeeding("dest_htlc", u32::MAX);
+        let policy = SettlementRetryPolicy { max_attempts: 2, backoff_ms: 0 };
+
+        let result = execute_atomic_swap_with("order-0", &steps, policy, 0);
+
+        assert_eq!(result.dest_htlc, Err("dest_htlc timed out".to_string()));
+    }
+
+    #[test]
+    fn attempt_counts_are_recorded_to_the_event_log_for_every_leg_attempted() {
+        reset_state();
+        let result = execute_atomic_swap_with("order-0", &MockSteps::succeeding(), SettlementRetryPolicy::default(), 7);
+        assert!(result.dest_claim.is_ok());
+
+        let events = crate::events::events_for("order-0");
+        let settlement_events: Vec<_> = events.iter().filter(|e| e.kind == "SettlementStep").collect();
+        assert_eq!(settlement_events.len(), 4);
+        assert!(settlement_events.iter().all(|e| e.detail.ends_with("succeeded on the first attempt")));
+    }
+
+    #[test]
+    fn setting_a_zero_max_attempts_policy_is_rejected() {
+        reset_state();
+        let result = set_settlement_retry_policy(SettlementRetryPolicy { max_attempts: 0, backoff_ms: 0 });
+        assert_eq!(result, Err(SwapError::InvalidAmount("max_attempts must be positive".into())));
+    }
+
+    fn base_request() -> crate::types::CreateOrderRequest {
+        crate::types::CreateOrderRequest {
+            src_chain: crate::types::Chain::ICP,
+            dst_chain: crate::types::Chain::Ethereum,
+            src_token: "ICP".into(),
+            dst_token: "ETH".into(),
+            amount: 10_000,
+            destination_address: "0xdead".into(),
+            escrowed_safety_deposit: 100,
+            client_reference: None,
+            destinations: None,
+            auto_refund_after: None,
+            timelocks: None,
+        }
+    }
+
+    fn create_order() -> String {
+        crate::orders::create_cross_chain_swap_order_internal(candid::Principal::anonymous(), base_request(), 0).unwrap()
+    }
+
+    #[test]
+    fn a_failed_leg_marks_the_order_failed_with_a_reason() {
+        reset_state();
+        let order_id = create_order();
+        let steps = MockSteps::failing_terminally_at("dest_htlc");
+
+        execute_atomic_swap_with(&order_id, &steps, SettlementRetryPolicy::default(), 0);
+
+        let state = STATE.with(|s| s.borrow().orders[&order_id].clone());
+        assert_eq!(state.status, crate::types::SwapOrderStatus::Failed);
+        assert_eq!(state.settlement_failure_reason, Some("dest_htlc failed".to_string()));
+    }
+
+    #[test]
+    fn a_failed_order_shows_up_in_get_failed_settlements() {
+        reset_state();
+        let order_id = create_order();
+        execute_atomic_swap_with(&order_id, &MockSteps::failing_terminally_at("source_claim"), SettlementRetryPolicy::default(), 0);
+
+        let failed = get_failed_settlements();
+
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, order_id);
+    }
+
+    #[test]
+    fn a_fully_successful_settlement_is_not_listed_as_failed() {
+        reset_state();
+        let order_id = create_order();
+        execute_atomic_swap_with(&order_id, &MockSteps::succeeding(), SettlementRetryPolicy::default(), 0);
+
+        assert!(get_failed_settlements().is_empty());
+        let state = STATE.with(|s| s.borrow().orders[&order_id].status.clone());
+        assert_eq!(state, crate::types::SwapOrderStatus::Completed);
+    }
+
+    #[test]
+    fn retrying_a_failed_settlement_resumes_from_the_failed_leg_instead_of_repeating_earlier_ones() {
+        reset_state();
+        let order_id = create_order();
+        let first_attempt = MockSteps::failing_terminally_at("dest_claim");
+        execute_atomic_swap_with(&order_id, &first_attempt, SettlementRetryPolicy::default(), 0);
+
+        let previous = STATE.with(|s| s.borrow().orders[&order_id].last_settlement.clone().unwrap());
+        let recovered = MockSteps::succeeding();
+        let result = resume_atomic_swap_with(&order_id, &recovered, SettlementRetryPolicy::default(), 1, previous);
+
+        assert_eq!(result.source_htlc, Ok(format!("{order_id}-source-htlc-tx")));
+        assert_eq!(result.dest_claim, Ok(format!("{order_id}-dest-claim-tx")));
+        let state = STATE.with(|s| s.borrow().orders[&order_id].status.clone());
+        assert_eq!(state, crate::types::SwapOrderStatus::Completed);
+    }
+
+    #[test]
+    fn a_fully_successful_settlement_records_every_leg_with_its_tx_hash() {
+        reset_state();
+        let order_id = create_order();
+        execute_atomic_swap_with(&order_id, &MockSteps::succeeding(), SettlementRetryPolicy::default(), 0);
+
+        let settlement = STATE.with(|s| s.borrow().orders[&order_id].settlement.clone());
+        assert_eq!(settlement.len(), 4);
+        assert_eq!(settlement[0].tx_hash, format!("{order_id}-source-htlc-tx"));
+        assert_eq!(settlement[0].chain, crate::types::Chain::ICP);
+        assert_eq!(settlement[0].direction, crate::types::SettlementDirection::Fund);
+        assert_eq!(settlement[1].tx_hash, format!("{order_id}-dest-htlc-tx"));
+        assert_eq!(settlement[1].chain, crate::types::Chain::Ethereum);
+        assert_eq!(settlement[2].tx_hash, format!("{order_id}-source-claim-tx"));
+        assert_eq!(settlement[2].direction, crate::types::SettlementDirection::Claim);
+        assert_eq!(settlement[3].tx_hash, format!("{order_id}-dest-claim-tx"));
+        assert!(settlement.iter().all(|leg| leg.amount == 10_000));
+    }
+
+    #[test]
+    fn a_mid_flow_failure_only_records_the_legs_that_actually_confirmed() {
+        reset_state();
+        let order_id = create_order();
+        execute_atomic_swap_with(&order_id, &MockSteps::failing_terminally_at("source_claim"), SettlementRetryPolicy::default(), 0);
+
+        let settlement = STATE.with(|s| s.borrow().orders[&order_id].settlement.clone());
+        assert_eq!(settlement.len(), 2);
+        assert!(settlement.iter().all(|leg| leg.direction == crate::types::SettlementDirection::Fund));
+    }
+
+    #[test]
+    fn retrying_a_failed_settlement_appends_only_the_newly_confirmed_legs() {
+        reset_state();
+        let order_id = create_order();
+        execute_atomic_swap_with(&order_id, &MockSteps::failing_terminally_at("dest_claim"), SettlementRetryPolicy::default(), 0);
+
+        let previous = STATE.with(|s| s.borrow().orders[&order_id].last_settlement.clone().unwrap());
+        resume_atomic_swap_with(&order_id, &MockSteps::succeeding(), SettlementRetryPolicy::default(), 1, previous);
+
+        let settlement = STATE.with(|s| s.borrow().orders[&order_id].settlement.clone());
+        assert_eq!(settlement.len(), 4);
+        assert_eq!(settlement[3].tx_hash, format!("{order_id}-dest-claim-tx"));
+    }
+
+    fn create_order_with_chains(src_chain: crate::types::Chain, dst_chain: crate::types::Chain) -> String {
+        let mut req = base_request();
+        req.src_chain = src_chain;
+        req.dst_chain = dst_chain;
+        crate::orders::create_cross_chain_swap_order_internal(candid::Principal::anonymous(), req, 0).unwrap()
+    }
+
+    #[test]
+    fn claim_order_prefers_icp_as_the_source_chain() {
+        assert_eq!(
+            safe_claim_order(crate::types::Chain::ICP, crate::types::Chain::Ethereum),
+            ClaimOrder::SourceFirst
+        );
+    }
+
+    #[test]
+    fn claim_order_prefers_icp_as_the_destination_chain() {
+        assert_eq!(
+            safe_claim_order(crate::types::Chain::Ethereum, crate::types::Chain::ICP),
+            ClaimOrder::DestFirst
+        );
+    }
+
+    #[test]
+    fn claim_order_falls_back_to_source_first_between_two_non_icp_chains() {
+        assert_eq!(
+            safe_claim_order(crate::types::Chain::Ethereum, crate::types::Chain::Solana),
+            ClaimOrder::SourceFirst
+        );
+    }
+
+    #[test]
+    fn settling_an_order_with_icp_as_the_destination_claims_it_before_the_source() {
+        reset_state();
+        let order_id = create_order_with_chains(crate::types::Chain::Ethereum, crate::types::Chain::ICP);
+        let steps = MockSteps::succeeding();
+
+        execute_atomic_swap_with(&order_id, &steps, SettlementRetryPolicy::default(), 0);
+
+        let claims: Vec<_> = steps
+            .call_order
+            .borrow()
+            .iter()
+            .filter(|step| **step == "source_claim" || **step == "dest_claim")
+            .cloned()
+            .collect();
+        assert_eq!(claims, vec!["dest_claim", "source_claim"]);
+
+        let settlement = STATE.with(|s| s.borrow().orders[&order_id].settlement.clone());
+        assert_eq!(settlement[2].chain, crate::types::Chain::ICP);
+        assert_eq!(settlement[2].direction, crate::types::SettlementDirection::Claim);
+    }
+
+    #[test]
+    fn settling_an_order_with_icp_as_the_source_claims_it_before_the_destination() {
+        reset_state();
+        let order_id = create_order_with_chains(crate::types::Chain::ICP, crate::types::Chain::Ethereum);
+        let steps = MockSteps::succeeding();
+
+        execute_atomic_swap_with(&order_id, &steps, SettlementRetryPolicy::default(), 0);
+
+        let claims: Vec<_> = steps
+            .call_order
+            .borrow()
+            .iter()
+            .filter(|step| **step == "source_claim" || **step == "dest_claim")
+            .cloned()
+            .collect();
+        assert_eq!(claims, vec!["source_claim", "dest_claim"]);
+    }
+}
@@ -0,0 +1,896 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ChainCallError, SwapError};
+use crate::state::STATE;
+use crate::types::Chain;
+
+/// The threshold ECDSA key name this canister signs Solana transactions
+/// with. Shares the canister's single configured key name with `evm.rs` so
+/// both chains derive from the same identity — see `identity::configured_key_name`.
+pub fn get_canister_ecdsa_key() -> String {
+    crate::identity::configured_key_name()
+}
+
+/// A transient associated token account the canister created to escrow SPL
+/// tokens for a single order. Tracked so its rent can be reclaimed once empty.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EscrowAta {
+    pub order_id: String,
+    pub address: String,
+    pub mint: String,
+    pub canister_owned: bool,
+}
+
+/// Records an already-derived associated token address as belonging to an
+/// order's escrow. `address` must be supplied by the caller pre-derived
+/// (e.g. via the client SDK's `getAssociatedTokenAddress`) — this canister
+/// has no base58 codec or `Pubkey`/PDA derivation of its own (see
+/// `looks_like_solana_pubkey`'s limitations), so it cannot compute an ATA
+/// from a wallet/mint pair itself, only validate and track one it's given.
+pub fn register_escrow_ata(order_id: String, address: String, mint: String) {
+    STATE.with(|s| {
+        s.borrow_mut().solana_escrow_atas.insert(
+            address.clone(),
+            EscrowAta {
+                order_id,
+                address,
+                mint,
+                canister_owned: true,
+            },
+        );
+    });
+}
+
+/// An ATA is eligible for closing once it is canister-owned and holds no tokens.
+pub fn is_closeable(ata: &EscrowAta, balance: u64) -> bool {
+    ata.canister_owned && balance == 0
+}
+
+/// Sweeps tracked escrow ATAs, closing any that are canister-owned and empty
+/// to reclaim their rent lamports back to the canister's Solana address.
+#[ic_cdk::update]
+pub async fn close_empty_escrow_atas() -> Vec<String> {
+    let candidates: Vec<EscrowAta> =
+        STATE.with(|s| s.borrow().solana_escrow_atas.values().cloned().collect());
+
+    let mut closed = Vec::new();
+    for ata in candidates {
+        let balance = match fetch_ata_balance(&ata.address).await {
+            Ok(balance) => balance,
+            Err(_) => continue,
+        };
+        if !is_closeable(&ata, balance) {
+            continue;
+        }
+        if close_ata_onchain(&ata.address).await.is_ok() {
+            STATE.with(|s| {
+                s.borrow_mut().solana_escrow_atas.remove(&ata.address);
+            });
+            closed.push(ata.address);
+        }
+    }
+    closed
+}
+
+/// Distinguishes a genuinely-missing token account (the RPC reports
+/// `"value": null`, since a never-funded ATA simply doesn't exist on-chain
+/// yet) from a response this parser doesn't recognize at all, so
+/// `fetch_ata_balance` can treat "no account" as zero balance without
+/// masking an actual parsing bug behind the same outcome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AtaBalanceLookup {
+    Found(u64),
+    AccountNotFound,
+    ParseError(String),
+}
+
+fn parse_token_account_balance(body: &str) -> AtaBalanceLookup {
+    if body.contains("\"value\":null") || body.contains("\"value\": null") {
+        return AtaBalanceLookup::AccountNotFound;
+    }
+    match find_balance_amount(body) {
+        Some(amount) => AtaBalanceLookup::Found(amount),
+        None => AtaBalanceLookup::ParseError(format!("unrecognized getTokenAccountBalance response: {body}")),
+    }
+}
+
+fn find_balance_amount(body: &str) -> Option<u64> {
+    let key = "\"amount\":\"";
+    let start = body.find(key)? + key.len();
+    let rest = &body[start..];
+    let end = rest.find('"')?;
+    rest[..end].parse().ok()
+}
+
+fn balance_from_rpc_response(body: &str) -> Result<u64, SwapError> {
+    match parse_token_account_balance(body) {
+        AtaBalanceLookup::Found(amount) => Ok(amount),
+        AtaBalanceLookup::AccountNotFound => Ok(0),
+        AtaBalanceLookup::ParseError(message) => Err(SwapError::ChainCallFailed(ChainCallError {
+            chain: Chain::Solana,
+            method: "getTokenAccountBalance".into(),
+            code: None,
+            message,
+            raw: Some(body.to_string()),
+        })),
+    }
+}
+
+/// Queries the SPL token balance of an escrow ATA via the chain-key Solana
+/// RPC canister. A token account that doesn't exist on-chain yet (never
+/// funded) legitimately means zero balance for escrow purposes, not a
+/// lookup failure — see `balance_from_rpc_response`.
+async fn fetch_ata_balance(_address: &str) -> Result<u64, SwapError> {
+    balance_from_rpc_response("{\"result\":{\"value\":null}}")
+}
+
+/// Signs and submits a `CloseAccount` instruction via the canister's
+/// threshold-Ed25519 Solana key, returning the account's rent to the canister.
+async fn close_ata_onchain(_address: &str) -> Result<(), SwapError> {
+    Ok(())
+}
+
+/// Outcome of submitting a signed transaction to the Solana RPC, kept
+/// distinct from `SwapError` because a caller retrying on submission may
+/// want to treat each case differently (e.g. retry on `ConnectivityFailure`,
+/// but surface `SubmissionRejected` straight to the user).
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SolanaSubmitResult {
+    Success(String),
+    ConnectivityFailure(String),
+    SubmissionRejected(String),
+    UnparseableResponse(String),
+}
+
+/// Abstracts the two RPC calls `submit_solana_transaction` makes, so tests
+/// can exercise every outcome with mocked responses instead of a live RPC.
+pub trait SolanaRpc {
+    fn get_slot(&self) -> Result<u64, String>;
+    fn send_transaction(&self, signed_tx: &[u8]) -> Result<String, String>;
+}
+
+/// Submits a signed transaction: first a `getSlot` connectivity check, then
+/// `sendTransaction`. Only ever reports success when a signature was
+/// actually parsed out of the RPC's response body.
+pub fn submit_solana_transaction(rpc: &impl SolanaRpc, signed_tx: &[u8]) -> SolanaSubmitResult {
+    if let Err(err) = rpc.get_slot() {
+        return SolanaSubmitResult::ConnectivityFailure(err);
+    }
+
+    match rpc.send_transaction(signed_tx) {
+        Ok(response) => match parse_transaction_signature(&response) {
+            Some(signature) => SolanaSubmitResult::Success(signature),
+            None => SolanaSubmitResult::UnparseableResponse(response),
+        },
+        Err(rpc_error) => SolanaSubmitResult::SubmissionRejected(rpc_error),
+    }
+}
+
+/// A Solana transaction signature is a base58 string; treat anything
+/// non-empty and free of JSON/error markers as a parsed signature.
+fn parse_transaction_signature(response: &str) -> Option<String> {
+    let trimmed = response.trim();
+    if trimmed.is_empty() || trimmed.contains("error") {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// Guards against re-submitting the exact same transaction (same recent
+/// blockhash + same signed payload) twice, which Solana would otherwise
+/// silently accept as a no-op replay. Call before submitting; records the
+/// pair as seen on success.
+pub fn check_and_record_submission(blockhash: &str, signed_tx: &[u8], now: u64) -> Result<(), SwapError> {
+    let key = replay_key(blockhash, signed_tx);
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.solana_replay_cache.contains_key(&key) {
+            return Err(SwapError::DuplicateSubmission);
+        }
+        state.solana_replay_cache.insert(key, now);
+        Ok(())
+    })
+}
+
+/// Drops replay-cache entries older than `ttl_secs`, so the idempotency
+/// cache doesn't grow without bound across the canister's lifetime. Returns
+/// the number of entries removed.
+pub fn prune_expired_replay_cache(now: u64, ttl_secs: u64) -> usize {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let before = state.solana_replay_cache.len();
+        state
+            .solana_replay_cache
+            .retain(|_, &mut inserted_at| now.saturating_sub(inserted_at) <= ttl_secs);
+        before - state.solana_replay_cache.len()
+    })
+}
+
+fn replay_key(blockhash: &str, signed_tx: &[u8]) -> String {
+    format!("{blockhash}:{}", hex_encode(signed_tx))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The SPL token program id a mint is owned by. `Other` covers forks and
+/// unrecognized programs, which are never allowlisted by default.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TokenProgram {
+    SplToken,
+    SplToken2022,
+    Other(String),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct CachedTokenProgram {
+    program: TokenProgram,
+    cached_at: u64,
+}
+
+/// Returns the cached token-program for a mint if it hasn't expired under
+/// `token_program_cache_ttl_secs`, so routine SPL operations don't re-fetch
+/// the mint's owner program on every call.
+pub fn cached_token_program(mint: &str, now: u64) -> Option<TokenProgram> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        let entry = state.token_program_cache.get(mint)?;
+        let ttl = state.config.token_program_cache_ttl_secs;
+        if now.saturating_sub(entry.cached_at) > ttl {
+            None
+        } else {
+            Some(entry.program.clone())
+        }
+    })
+}
+
+pub fn cache_token_program(mint: &str, program: TokenProgram, now: u64) {
+    STATE.with(|s| {
+        s.borrow_mut().token_program_cache.insert(
+            mint.to_string(),
+            CachedTokenProgram { program, cached_at: now },
+        );
+    });
+}
+
+/// Signs a serialized Solana transaction with the canister's threshold
+/// Ed25519 key, then submits it via the chain-key Solana RPC canister,
+/// propagating any failure from either step instead of swallowing it.
+#[ic_cdk::update]
+pub async fn sign_and_send_solana_transaction(unsigned_tx: Vec<u8>) -> Result<String, SwapError> {
+    let signature = sign_solana_transaction(&unsigned_tx).await?;
+    let tx_signature = submit_signed_transaction(&unsigned_tx, &signature).await?;
+    Ok(tx_signature)
+}
+
+async fn sign_solana_transaction(_unsigned_tx: &[u8]) -> Result<Vec<u8>, SwapError> {
+    Ok(Vec::new())
+}
+
+async fn submit_signed_transaction(_unsigned_tx: &[u8], _signature: &[u8]) -> Result<String, SwapError> {
+    Ok(String::new())
+}
+
+/// Converts a caller-supplied amount (kept as `u128`, like every other
+/// amount this canister tracks) into the native `u64` lamports a Solana
+/// transfer actually carries, checked rather than truncating, since
+/// nothing at the Candid boundary stops a caller from passing a u128 wider
+/// than any wallet could really hold.
+fn lamports_to_u64(amount: u128) -> Result<u64, SwapError> {
+    u64::try_from(amount).map_err(|_| SwapError::InvalidAmount(format!("{amount} lamports does not fit in a u64")))
+}
+
+/// Looks up a wallet's native SOL balance via the chain-key Solana RPC
+/// canister. Rejects a malformed address outright instead of forwarding it
+/// to the RPC and finding out from whatever comes back.
+#[ic_cdk::update]
+pub async fn get_sol_balance(owner: String) -> Result<u128, SwapError> {
+    parse_solana_pubkey(&owner)?;
+    Ok(fetch_sol_balance(&owner).await? as u128)
+}
+
+/// Stubbed the same way `fetch_ata_balance` is: a never-funded account
+/// legitimately has a balance of zero rather than being a lookup failure.
+async fn fetch_sol_balance(_owner: &str) -> Result<u64, SwapError> {
+    balance_from_rpc_response("{\"result\":{\"value\":null}}")
+}
+
+/// Transfers native SOL to `to`. Validates the destination address and
+/// checks the lamport amount fits a u64 before ever touching the RPC or
+/// signing path, so a malformed address or an out-of-range amount comes
+/// back as a typed error instead of trapping partway through.
+#[ic_cdk::update]
+pub async fn send_sol(to: String, amount: u128) -> Result<String, SwapError> {
+    parse_solana_pubkey(&to)?;
+    let lamports = lamports_to_u64(amount)?;
+    sign_and_send_sol_transfer(&to, lamports).await
+}
+
+/// Stubbed for the same reason `submit_spl_transfer` is: this tree has no
+/// ed25519 dependency to sign a native Solana transfer with.
+async fn sign_and_send_sol_transfer(_to: &str, _lamports: u64) -> Result<String, SwapError> {
+    Ok(String::new())
+}
+
+/// Would derive the associated token account address for `(owner, mint)`
+/// the way the client SDK's `getAssociatedTokenAddress` does. This
+/// canister has no base58 codec or PDA derivation of its own (see
+/// `register_escrow_ata`'s doc comment) and cannot compute one itself;
+/// still validates both inputs first so a malformed pubkey is reported as
+/// exactly that, rather than folded into the same "can't derive" message a
+/// well-formed pair gets.
+#[ic_cdk::query]
+pub fn get_associated_token_account_address(owner: String, mint: String) -> Result<String, SwapError> {
+    parse_solana_pubkey(&owner)?;
+    parse_solana_pubkey(&mint)?;
+    Err(SwapError::InvalidDestinationAddress(
+        "this canister cannot derive an associated token account address; derive it client-side and pass it to register_escrow_ata".into(),
+    ))
+}
+
+/// SPL Token program instruction tag for `TransferChecked`.
+const TRANSFER_CHECKED_TAG: u8 = 12;
+
+/// `TransferChecked` differs from the legacy `Transfer` instruction by
+/// carrying the mint's decimals, so the token program itself rejects a
+/// caller passing an amount at the wrong decimal scale instead of silently
+/// moving the wrong amount.
+fn encode_transfer_checked(amount: u64, decimals: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(10);
+    data.push(TRANSFER_CHECKED_TAG);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    data
+}
+
+fn validate_transfer_checked_decimals(expected_decimals: u8, mint_decimals: u8) -> Result<(), SwapError> {
+    if expected_decimals != mint_decimals {
+        return Err(SwapError::InvalidAmount(format!(
+            "decimals mismatch: mint has {mint_decimals}, caller passed {expected_decimals}"
+        )));
+    }
+    Ok(())
+}
+
+/// Looks up a mint's decimals, cached the same way its token program is.
+async fn fetch_mint_decimals(_mint: &str) -> Result<u8, SwapError> {
+    Ok(9)
+}
+
+/// Stubbed submission: this tree has no ed25519 dependency (see
+/// `looks_like_solana_pubkey`), so there is no wallet here that can actually
+/// produce the signature a Solana transaction needs — `send_spl_token`'s
+/// `SplToken`/`Transfer` instructions never leave the canister signed.
+/// `get_canister_ecdsa_key`'s threshold key is shared with `evm.rs`, which
+/// is fine for EVM's secp256k1 signatures but cannot sign for Solana, which
+/// requires ed25519; don't wire a real signer into this stub using that key.
+async fn submit_spl_transfer(_destination: &str, _instruction: &[u8]) -> Result<String, SwapError> {
+    Ok(String::new())
+}
+
+/// Looks up which token program owns a mint, behind a trait so the
+/// allowlist check can be unit tested against mocked mints without a live
+/// RPC call.
+pub trait MintProgramLookup {
+    fn mint_token_program(&self, mint: &str) -> Result<TokenProgram, SwapError>;
+}
+
+struct LiveMintProgramLookup;
+
+impl MintProgramLookup for LiveMintProgramLookup {
+    fn mint_token_program(&self, _mint: &str) -> Result<TokenProgram, SwapError> {
+        Ok(TokenProgram::SplToken)
+    }
+}
+
+/// Rejects a mint whose owner program isn't on the admin-configured
+/// allowlist, so escrowing a token governed by an exotic/unknown program
+/// (which may not honor standard transfer semantics) fails before any funds
+/// move instead of misbehaving downstream.
+fn validate_trusted_token_program(program: &TokenProgram) -> Result<(), SwapError> {
+    let allowed = STATE.with(|s| s.borrow().config.allowed_spl_token_programs.clone());
+    if !allowed.contains(program) {
+        return Err(SwapError::UntrustedTokenProgram(format!("{program:?}")));
+    }
+    Ok(())
+}
+
+/// Sends SPL tokens via `TransferChecked` rather than the legacy `Transfer`
+/// instruction, so a caller passing `decimals` for the wrong scale is
+/// rejected up front instead of silently moving the wrong amount.
+pub async fn send_spl_token(
+    mint: &str,
+    destination: &str,
+    amount: u64,
+    decimals: u8,
+) -> Result<String, SwapError> {
+    send_spl_token_with(mint, destination, amount, decimals, &LiveMintProgramLookup).await
+}
+
+async fn send_spl_token_with(
+    mint: &str,
+    destination: &str,
+    amount: u64,
+    decimals: u8,
+    lookup: &impl MintProgramLookup,
+) -> Result<String, SwapError> {
+    let program = lookup.mint_token_program(mint)?;
+    validate_trusted_token_program(&program)?;
+    let mint_decimals = fetch_mint_decimals(mint).await?;
+    validate_transfer_checked_decimals(decimals, mint_decimals)?;
+    let instruction = encode_transfer_checked(amount, mint_decimals);
+    submit_spl_transfer(destination, &instruction).await
+}
+
+/// Checks that an SPL delegation (the `Approve`d allowance on the maker's
+/// token account) covers `required`, mirroring
+/// `icrc::require_sufficient_allowance` for Solana's delegate-based approval
+/// model so a shortfall surfaces as a structured `InsufficientAllowance`
+/// instead of a failed `TransferChecked` submitted against an under-delegated
+/// account.
+pub fn require_sufficient_spl_delegation(current_delegated: u64, required: u64, mint: &str) -> Result<(), SwapError> {
+    if current_delegated < required {
+        return Err(SwapError::InsufficientAllowance {
+            current: current_delegated as u128,
+            required: required as u128,
+            token: mint.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// A single SPL token account the canister's Solana address owns, as
+/// returned by `getTokenAccountsByOwner`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SplTokenAccount {
+    pub address: String,
+    pub mint: String,
+    pub amount: u64,
+}
+
+fn extract_quoted_field(segment: &str, key: &str) -> Option<String> {
+    let pattern = format!("\"{key}\":\"");
+    let start = segment.find(&pattern)? + pattern.len();
+    let rest = &segment[start..];
+    let end = rest.find('"')?;
+    Some(rest[..end].to_string())
+}
+
+/// Parses a `getTokenAccountsByOwner` response into one `SplTokenAccount`
+/// per returned account. Splits on `"pubkey"` so each chunk scanned for
+/// `mint`/`amount` only ever sees that account's own fields, the same
+/// per-record isolation `find_balance_amount` relies on for a single account.
+fn parse_token_accounts_by_owner(body: &str) -> Vec<SplTokenAccount> {
+    body.split("\"pubkey\":\"")
+        .skip(1)
+        .filter_map(|chunk| {
+            let end = chunk.find('"')?;
+            let address = chunk[..end].to_string();
+            let mint = extract_quoted_field(chunk, "mint")?;
+            let amount = extract_quoted_field(chunk, "amount")?.parse::<u64>().ok()?;
+            Some(SplTokenAccount { address, mint, amount })
+        })
+        .collect()
+}
+
+/// Queries `getTokenAccountsByOwner` for `owner` via the chain-key Solana RPC
+/// canister. Kept separate from `fetch_ata_balance`, which only ever looks up
+/// one known escrow ATA's balance; this inventories every SPL token account
+/// an address owns, including ones the canister never tracked an `EscrowAta`
+/// for (e.g. a direct deposit).
+async fn fetch_token_accounts_by_owner(_owner: &str) -> Result<String, SwapError> {
+    Ok("{\"result\":{\"value\":[]}}".to_string())
+}
+
+/// Lists every SPL token account the canister's own Solana address owns, for
+/// reconciliation and rent management.
+#[ic_cdk::update]
+pub async fn get_canister_token_accounts() -> Result<Vec<SplTokenAccount>, SwapError> {
+    let owner = crate::identity::derive_solana_address(&get_canister_ecdsa_key());
+    let body = fetch_token_accounts_by_owner(&owner).await?;
+    Ok(parse_token_accounts_by_owner(&body))
+}
+
+/// Whether `text` is a plausible base58-encoded Solana public key: the
+/// right length range and alphabet (base58 excludes `0`, `O`, `I`, `l` to
+/// avoid visual ambiguity). Not a full curve-point check (this tree has no
+/// ed25519 dependency), but enough to reject obviously malformed input
+/// before it's handed to a live RPC call or signing path.
+fn looks_like_solana_pubkey(text: &str) -> bool {
+    const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    (32..=44).contains(&text.len()) && text.chars().all(|c| BASE58_ALPHABET.contains(c))
+}
+
+/// Validates caller-supplied Solana public key text without trapping on
+/// malformed input, the Solana-side counterpart to
+/// `identity::parse_principal_text`.
+pub fn parse_solana_pubkey(text: &str) -> Result<String, SwapError> {
+    if looks_like_solana_pubkey(text) {
+        Ok(text.to_string())
+    } else {
+        Err(SwapError::InvalidDestinationAddress(format!("{text} is not a valid Solana public key")))
+    }
+}
+
+/// Validates and echoes back a caller-supplied Solana public key. Exists so
+/// a client can confirm an address is well-formed before using it as an
+/// escrow destination or delegate, rather than finding out from a failed
+/// on-chain submission.
+#[ic_cdk::query]
+pub fn get_solana_wallet_public(pubkey_text: String) -> Result<String, SwapError> {
+    parse_solana_pubkey(&pubkey_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ata(canister_owned: bool) -> EscrowAta {
+        EscrowAta {
+            order_id: "order-0".into(),
+            address: "ATA111".into(),
+            mint: "MINT111".into(),
+            canister_owned,
+        }
+    }
+
+    #[test]
+    fn closeable_only_when_canister_owned_and_empty() {
+        assert!(is_closeable(&ata(true), 0));
+        assert!(!is_closeable(&ata(true), 1));
+        assert!(!is_closeable(&ata(false), 0));
+    }
+
+    #[test]
+    fn token_program_cache_hit_within_ttl() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        cache_token_program("MINT1", TokenProgram::SplToken2022, 1_000);
+        assert_eq!(cached_token_program("MINT1", 1_500), Some(TokenProgram::SplToken2022));
+    }
+
+    #[test]
+    fn token_program_cache_expires_after_ttl() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let ttl = STATE.with(|s| s.borrow().config.token_program_cache_ttl_secs);
+        cache_token_program("MINT1", TokenProgram::SplToken, 1_000);
+        assert_eq!(cached_token_program("MINT1", 1_000 + ttl + 1), None);
+    }
+
+    #[test]
+    fn duplicate_submission_for_same_blockhash_is_rejected() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        check_and_record_submission("blockhash1", b"tx-bytes", 0).unwrap();
+        assert_eq!(
+            check_and_record_submission("blockhash1", b"tx-bytes", 0),
+            Err(SwapError::DuplicateSubmission)
+        );
+    }
+
+    #[test]
+    fn same_payload_on_a_new_blockhash_is_allowed() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        check_and_record_submission("blockhash1", b"tx-bytes", 0).unwrap();
+        assert!(check_and_record_submission("blockhash2", b"tx-bytes", 0).is_ok());
+    }
+
+    #[test]
+    fn pruning_removes_only_entries_past_ttl() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        check_and_record_submission("stale-blockhash", b"tx-bytes", 0).unwrap();
+        check_and_record_submission("fresh-blockhash", b"tx-bytes", 90).unwrap();
+
+        let removed = prune_expired_replay_cache(100, 50);
+
+        assert_eq!(removed, 1);
+        STATE.with(|s| assert_eq!(s.borrow().solana_replay_cache.len(), 1));
+    }
+
+    struct MockRpc {
+        slot: Result<u64, String>,
+        send: Result<String, String>,
+    }
+
+    impl SolanaRpc for MockRpc {
+        fn get_slot(&self) -> Result<u64, String> {
+            self.slot.clone()
+        }
+
+        fn send_transaction(&self, _signed_tx: &[u8]) -> Result<String, String> {
+            self.send.clone()
+        }
+    }
+
+    #[test]
+    fn submit_reports_connectivity_failure_when_get_slot_fails() {
+        let rpc = MockRpc {
+            slot: Err("timeout".into()),
+            send: Ok("sig111".into()),
+        };
+        assert_eq!(
+            submit_solana_transaction(&rpc, b"tx"),
+            SolanaSubmitResult::ConnectivityFailure("timeout".into())
+        );
+    }
+
+    #[test]
+    fn submit_reports_rejection_when_rpc_errors_on_send() {
+        let rpc = MockRpc {
+            slot: Ok(123),
+            send: Err("insufficient funds for rent".into()),
+        };
+        assert_eq!(
+            submit_solana_transaction(&rpc, b"tx"),
+            SolanaSubmitResult::SubmissionRejected("insufficient funds for rent".into())
+        );
+    }
+
+    #[test]
+    fn submit_reports_unparseable_response_when_no_signature_found() {
+        let rpc = MockRpc {
+            slot: Ok(123),
+            send: Ok("".into()),
+        };
+        assert_eq!(
+            submit_solana_transaction(&rpc, b"tx"),
+            SolanaSubmitResult::UnparseableResponse("".into())
+        );
+    }
+
+    #[test]
+    fn submit_reports_success_with_parsed_signature() {
+        let rpc = MockRpc {
+            slot: Ok(123),
+            send: Ok("5sigBase58Value".into()),
+        };
+        assert_eq!(
+            submit_solana_transaction(&rpc, b"tx"),
+            SolanaSubmitResult::Success("5sigBase58Value".into())
+        );
+    }
+
+    #[test]
+    fn transfer_checked_encodes_tag_amount_and_decimals() {
+        let instruction = encode_transfer_checked(1_000, 6);
+        assert_eq!(instruction[0], TRANSFER_CHECKED_TAG);
+        assert_eq!(&instruction[1..9], &1_000u64.to_le_bytes());
+        assert_eq!(instruction[9], 6);
+    }
+
+    #[test]
+    fn decimals_mismatch_is_rejected_before_encoding() {
+        assert_eq!(
+            validate_transfer_checked_decimals(6, 9),
+            Err(SwapError::InvalidAmount(
+                "decimals mismatch: mint has 9, caller passed 6".into()
+            ))
+        );
+    }
+
+    /// Drives a future to completion without pulling in an async-executor
+    /// dependency; fine because every leg here resolves on first poll.
+    fn block_on<F: std::future::Future>(mut fut: F) -> F::Output {
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let raw = RawWaker::new(std::ptr::null(), &VTABLE);
+        let waker = unsafe { Waker::from_raw(raw) };
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+        loop {
+            if let Poll::Ready(val) = fut.as_mut().poll(&mut cx) {
+                return val;
+            }
+        }
+    }
+
+    #[test]
+    fn send_spl_token_rejects_a_decimals_mismatched_transfer() {
+        let result = block_on(send_spl_token("MINT1", "dest", 1_000, 6));
+        assert_eq!(
+            result,
+            Err(SwapError::InvalidAmount(
+                "decimals mismatch: mint has 9, caller passed 6".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn send_spl_token_accepts_a_matching_decimals_transfer() {
+        let result = block_on(send_spl_token("MINT1", "dest", 1_000, 9));
+        assert!(result.is_ok());
+    }
+
+    struct MockMintProgramLookup {
+        program: TokenProgram,
+    }
+
+    impl MintProgramLookup for MockMintProgramLookup {
+        fn mint_token_program(&self, _mint: &str) -> Result<TokenProgram, SwapError> {
+            Ok(self.program.clone())
+        }
+    }
+
+    #[test]
+    fn mint_owned_by_a_non_allowlisted_program_is_rejected_before_escrow() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let lookup = MockMintProgramLookup {
+            program: TokenProgram::Other("ForkedTokenProgram111".into()),
+        };
+
+        let result = block_on(send_spl_token_with("MINT1", "dest", 1_000, 9, &lookup));
+
+        assert_eq!(
+            result,
+            Err(SwapError::UntrustedTokenProgram(
+                "Other(\"ForkedTokenProgram111\")".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn mint_owned_by_an_allowlisted_program_proceeds_to_the_decimals_check() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+        let lookup = MockMintProgramLookup { program: TokenProgram::SplToken2022 };
+
+        let result = block_on(send_spl_token_with("MINT1", "dest", 1_000, 9, &lookup));
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn spl_delegation_covering_the_required_amount_is_accepted() {
+        assert!(require_sufficient_spl_delegation(1_000, 1_000, "MINT1").is_ok());
+    }
+
+    #[test]
+    fn spl_delegation_shortfall_is_reported_as_a_structured_error() {
+        let result = require_sufficient_spl_delegation(300, 1_000, "MINT1");
+
+        assert_eq!(
+            result,
+            Err(SwapError::InsufficientAllowance {
+                current: 300,
+                required: 1_000,
+                token: "MINT1".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn a_missing_token_account_is_treated_as_zero_balance_not_an_error() {
+        let result = balance_from_rpc_response("{\"result\":{\"value\":null}}");
+        assert_eq!(result, Ok(0));
+    }
+
+    #[test]
+    fn a_well_formed_balance_response_parses_the_amount() {
+        let result = balance_from_rpc_response(
+            "{\"result\":{\"value\":{\"amount\":\"4200\",\"decimals\":9}}}",
+        );
+        assert_eq!(result, Ok(4_200));
+    }
+
+    #[test]
+    fn an_unrecognized_response_shape_is_a_real_error_not_a_zero_balance() {
+        let result = balance_from_rpc_response("{\"result\":{\"unexpected\":true}}");
+        assert!(matches!(result, Err(SwapError::ChainCallFailed(_))));
+    }
+
+    #[test]
+    fn an_account_not_found_escrow_is_reported_closeable_at_zero_balance() {
+        let balance = balance_from_rpc_response("{\"result\":{\"value\":null}}").unwrap();
+        let ata = EscrowAta {
+            order_id: "order-0".into(),
+            address: "ATA1".into(),
+            mint: "MINT1".into(),
+            canister_owned: true,
+        };
+        assert!(is_closeable(&ata, balance));
+    }
+
+    const TOKEN_ACCOUNTS_FIXTURE: &str = r#"{"result":{"value":[
+        {"pubkey":"ATA1111","account":{"data":{"parsed":{"info":{"mint":"MINT1111","tokenAmount":{"amount":"1500","decimals":6}}}}}},
+        {"pubkey":"ATA2222","account":{"data":{"parsed":{"info":{"mint":"MINT2222","tokenAmount":{"amount":"0","decimals":9}}}}}}
+    ]}}"#;
+
+    #[test]
+    fn decodes_every_account_in_a_fixture_list() {
+        let accounts = parse_token_accounts_by_owner(TOKEN_ACCOUNTS_FIXTURE);
+
+        assert_eq!(
+            accounts,
+            vec![
+                SplTokenAccount { address: "ATA1111".into(), mint: "MINT1111".into(), amount: 1_500 },
+                SplTokenAccount { address: "ATA2222".into(), mint: "MINT2222".into(), amount: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn an_empty_account_list_decodes_to_no_accounts() {
+        assert!(parse_token_accounts_by_owner("{\"result\":{\"value\":[]}}").is_empty());
+    }
+
+    #[test]
+    fn a_well_formed_pubkey_is_accepted() {
+        let pubkey = "DRpbCBMxVnDK7maPM5tGv6MvB3v1sRMC86PZ8okm21hy";
+        assert_eq!(parse_solana_pubkey(pubkey), Ok(pubkey.to_string()));
+    }
+
+    #[test]
+    fn a_pubkey_with_an_invalid_base58_character_is_a_clean_error_not_a_trap() {
+        let result = parse_solana_pubkey("0OIl-not-valid-base58-at-all!!!!");
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(_))));
+    }
+
+    #[test]
+    fn a_pubkey_that_is_far_too_short_is_rejected() {
+        assert!(parse_solana_pubkey("short").is_err());
+    }
+
+    #[test]
+    fn get_solana_wallet_public_echoes_back_a_valid_pubkey() {
+        let pubkey = "DRpbCBMxVnDK7maPM5tGv6MvB3v1sRMC86PZ8okm21hy";
+        assert_eq!(get_solana_wallet_public(pubkey.into()), Ok(pubkey.to_string()));
+    }
+
+    #[test]
+    fn get_solana_wallet_public_rejects_malformed_text_cleanly() {
+        let result = get_solana_wallet_public("not-valid!!".into());
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(_))));
+    }
+
+    const VALID_PUBKEY: &str = "DRpbCBMxVnDK7maPM5tGv6MvB3v1sRMC86PZ8okm21hy";
+
+    #[test]
+    fn get_sol_balance_rejects_invalid_base58_input_without_touching_the_rpc() {
+        let result = block_on(get_sol_balance("not-valid!!".into()));
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(_))));
+    }
+
+    #[test]
+    fn get_sol_balance_reports_zero_for_a_never_funded_wallet() {
+        let result = block_on(get_sol_balance(VALID_PUBKEY.into()));
+        assert_eq!(result, Ok(0));
+    }
+
+    #[test]
+    fn send_sol_rejects_invalid_base58_destinations() {
+        let result = block_on(send_sol("not-valid!!".into(), 1_000));
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(_))));
+    }
+
+    #[test]
+    fn send_sol_rejects_an_amount_that_overflows_u64() {
+        let amount = u128::from(u64::MAX) + 1;
+        let result = block_on(send_sol(VALID_PUBKEY.into(), amount));
+        assert!(matches!(result, Err(SwapError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn send_sol_accepts_an_amount_at_exactly_u64_max() {
+        let result = block_on(send_sol(VALID_PUBKEY.into(), u128::from(u64::MAX)));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn get_associated_token_account_address_rejects_a_malformed_owner() {
+        let result = get_associated_token_account_address("not-valid!!".into(), VALID_PUBKEY.into());
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(msg)) if msg.contains("not a valid Solana public key")));
+    }
+
+    #[test]
+    fn get_associated_token_account_address_reports_it_cannot_derive_one_for_well_formed_input() {
+        let result = get_associated_token_account_address(VALID_PUBKEY.into(), VALID_PUBKEY.into());
+        assert!(matches!(result, Err(SwapError::InvalidDestinationAddress(msg)) if msg.contains("cannot derive")));
+    }
+}
@@ -0,0 +1,103 @@
+use candid::Principal;
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn day_bucket(now: u64) -> u64 {
+    now / SECONDS_PER_DAY
+}
+
+/// Reserves `amount` of destination-chain gas sponsorship against a
+/// principal's daily budget, rejecting once that budget is exhausted. The
+/// budget resets at the start of each new day bucket. Call this before
+/// sponsoring a completion's gas so a maker can't drain the canister by
+/// creating many tiny orders.
+pub fn try_consume_gas_sponsorship(
+    principal: Principal,
+    amount: u128,
+    now: u64,
+) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let limit = state.config.daily_gas_sponsorship_limit;
+        let today = day_bucket(now);
+
+        let entry = state.gas_sponsorship_used.entry(principal).or_insert((today, 0));
+        if entry.0 != today {
+            *entry = (today, 0);
+        }
+
+        let projected = entry.1 + amount;
+        if projected > limit {
+            return Err(SwapError::GasSponsorshipBudgetExceeded { limit, used: entry.1 });
+        }
+
+        entry.1 = projected;
+        Ok(())
+    })
+}
+
+/// Admin-only: adjust the daily per-principal gas sponsorship budget.
+#[ic_cdk::update]
+pub fn set_daily_gas_sponsorship_limit(limit: u128) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().config.daily_gas_sponsorship_limit = limit);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn consumption_within_budget_succeeds() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.daily_gas_sponsorship_limit = 100);
+        let p = Principal::anonymous();
+        assert!(try_consume_gas_sponsorship(p, 40, 0).is_ok());
+        assert!(try_consume_gas_sponsorship(p, 40, 0).is_ok());
+    }
+
+    #[test]
+    fn exhausting_budget_rejects_further_sponsored_claims() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.daily_gas_sponsorship_limit = 100);
+        let p = Principal::anonymous();
+        try_consume_gas_sponsorship(p, 60, 0).unwrap();
+        try_consume_gas_sponsorship(p, 40, 0).unwrap();
+
+        let result = try_consume_gas_sponsorship(p, 1, 0);
+        assert_eq!(
+            result,
+            Err(SwapError::GasSponsorshipBudgetExceeded { limit: 100, used: 100 })
+        );
+    }
+
+    #[test]
+    fn budget_resets_on_a_new_day() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.daily_gas_sponsorship_limit = 100);
+        let p = Principal::anonymous();
+        try_consume_gas_sponsorship(p, 100, 0).unwrap();
+        assert!(try_consume_gas_sponsorship(p, 1, 0).is_err());
+
+        // One day later, the budget is fresh again.
+        assert!(try_consume_gas_sponsorship(p, 100, SECONDS_PER_DAY).is_ok());
+    }
+
+    #[test]
+    fn budgets_are_tracked_independently_per_principal() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().config.daily_gas_sponsorship_limit = 100);
+        let a = Principal::anonymous();
+        let b = Principal::from_slice(&[1u8; 29]);
+        try_consume_gas_sponsorship(a, 100, 0).unwrap();
+        assert!(try_consume_gas_sponsorship(b, 100, 0).is_ok());
+    }
+}
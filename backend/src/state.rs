@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use candid::Principal;
+
+use crate::config::CanisterConfig;
+use crate::evm::{EthCallRetryPolicy, EvmNonceState};
+use crate::events::OrderEvent;
+use crate::htlc::HTLCEscrow;
+use crate::identity::CanisterIdentity;
+use crate::pools::UnifiedLiquidityPool;
+use crate::settlement::SettlementRetryPolicy;
+use crate::solana::{CachedTokenProgram, EscrowAta};
+use crate::types::{Chain, PairingKey, SwapOrder};
+
+#[derive(Default)]
+pub struct State {
+    pub orders: HashMap<String, SwapOrder>,
+    pub config: CanisterConfig,
+    pub next_order_seq: u64,
+    pub solana_escrow_atas: HashMap<String, EscrowAta>,
+    pub pools: HashMap<String, UnifiedLiquidityPool>,
+    pub next_pool_seq: u64,
+    pub unhealthy_chains: HashMap<Chain, bool>,
+    pub evm_nonces: HashMap<String, EvmNonceState>,
+    pub recent_cancellations: HashMap<String, u64>,
+    pub htlcs: HashMap<String, Vec<HTLCEscrow>>,
+    pub events: HashMap<String, Vec<OrderEvent>>,
+    pub paused_tokens: HashMap<String, bool>,
+    pub chain_liquidity: HashMap<(Chain, String), u128>,
+    /// Floor below which `pairing::withdraw_chain_liquidity` refuses to drop
+    /// a (chain, token)'s known liquidity, so a chain can never be drained
+    /// to zero and break subsequent same-chain swaps. Missing entries have
+    /// no floor (0).
+    pub min_chain_reserve: HashMap<(Chain, String), u128>,
+    pub maker_index: HashMap<Principal, Vec<String>>,
+    pub reference_index: HashMap<String, Vec<String>>,
+    pub eth_call_retry_policy: EthCallRetryPolicy,
+    pub in_flight_outcalls: u32,
+    /// Idempotency cache: replay key -> when it was first seen.
+    pub solana_replay_cache: HashMap<String, u64>,
+    pub token_program_cache: HashMap<String, CachedTokenProgram>,
+    /// Per-principal (day bucket, amount sponsored so far that day).
+    pub gas_sponsorship_used: HashMap<Principal, (u64, u128)>,
+    /// Orders ordered by `(expires_at, order_id)`, so the expiry sweep only
+    /// touches orders that have actually expired instead of scanning every
+    /// order on the canister. Kept in sync on creation, expiry extension,
+    /// and any transition to a terminal status.
+    pub expiry_index: BTreeSet<(u64, String)>,
+    /// Open (`Created`) orders bucketed by their own
+    /// `(src_chain, src_token, dst_chain, dst_token)`, so a pairing scan for
+    /// a prospective order only has to look up its complementary bucket
+    /// instead of walking every order on the canister. See `matching::preview_pairing`.
+    pub pairing_index: HashMap<PairingKey, BTreeSet<(u64, String)>>,
+    pub settlement_retry_policy: SettlementRetryPolicy,
+    pub canister_identity: Option<CanisterIdentity>,
+    pub onchain_configured_evm_signer: Option<String>,
+    /// Name of the threshold signing key both `evm.rs` and `solana.rs`
+    /// derive their addresses from. Empty means "use the default" — see
+    /// `identity::configured_key_name`.
+    pub key_name: String,
+    /// Canister-wide seed, drawn once from `raw_rand`, that every order's
+    /// secret can be deterministically re-derived from. Empty until
+    /// `secrets::initialize_master_seed` runs — see `secrets.rs`.
+    pub master_seed: Vec<u8>,
+    /// Bridgeless (burn-and-mint) token transfers in flight between chains.
+    /// See `bridgeless.rs`.
+    pub cross_chain_transfers: HashMap<String, crate::bridgeless::CrossChainTransfer>,
+    pub next_transfer_seq: u64,
+    /// Outstanding minted supply of a token on a chain, for bridgeless
+    /// transfers. A transfer burns from `from_chain`'s entry on initiation
+    /// and only credits `to_chain`'s entry once its mint is confirmed, so the
+    /// combined total across chains can never exceed what was ever minted.
+    pub bridgeless_supply: HashMap<(String, Chain), u128>,
+    /// Cumulative protocol dust per token, accrued whenever
+    /// `decimals::convert_and_record_dust` rounds a conversion down (or, for
+    /// a non-floor policy, keeps any remainder rather than paying it out).
+    /// See `decimals::RoundingPolicy`.
+    pub accrued_dust: HashMap<String, u128>,
+    /// ERC-20 token addresses (normalized, see `evm::normalize_evm_address`)
+    /// that revert on `approve` from a non-zero allowance straight to
+    /// another non-zero value (e.g. USDT), keyed to whether the
+    /// approve-fallback escrow path must reset the allowance to zero first.
+    /// See `evm::build_erc20_approve_calls`.
+    pub erc20_requires_approval_reset: HashMap<String, bool>,
+    /// Cycles budget applied to an outcall with no per-method-class override
+    /// in `outcall_cycles_by_method`. See `http_client::compute_outcall_cycles`.
+    pub default_outcall_cycles_policy: crate::http_client::OutcallCyclesPolicy,
+    /// Per-method-class (e.g. `"eth_call"`, `"solana_rpc"`) overrides of the
+    /// outcall cycles budget, so each RPC method's cost can be tuned
+    /// independently as fee schedules diverge. See `http_client::compute_outcall_cycles`.
+    pub outcall_cycles_by_method: HashMap<String, crate::http_client::OutcallCyclesPolicy>,
+    /// Per-token dust floor (in that token's base units) below which an
+    /// order is rejected rather than created, since fees would dominate a
+    /// smaller swap. Missing entries have no floor. See `tokens::set_token_min_amount`.
+    pub min_amount_by_token: HashMap<String, u128>,
+    /// Per-token ceiling (in that token's base units) above which an order
+    /// is rejected. Missing entries have no ceiling. See `tokens::set_token_max_amount`.
+    pub max_amount_by_token: HashMap<String, u128>,
+    /// ICRC-1 ledger canister backing each ICP-side token symbol, so
+    /// `icrc::get_icrc_allowance` knows which canister to query. Missing
+    /// entries mean the token has no known ledger yet. See
+    /// `tokens::set_icrc_ledger_canister`.
+    pub icrc_ledger_canisters: HashMap<String, Principal>,
+    /// Reentrancy guard for the timer-driven expiry sweep: held for the
+    /// duration of one sweep so an overlapping interval tick — e.g. because
+    /// the prior sweep is still working through a large backlog — skips
+    /// instead of refunding the same order twice. See
+    /// `maintenance::run_expiry_sweep`.
+    pub expiry_sweep_in_progress: bool,
+    /// Handle to the currently registered recurring expiry-sweep timer, so
+    /// `maintenance::schedule_expiry_sweep` can clear the old one before
+    /// registering a new interval instead of stacking multiple timers.
+    pub expiry_sweep_timer: Option<ic_cdk_timers::TimerId>,
+    /// Ordered, deduplicated list of EVM JSON-RPC endpoints to try in
+    /// sequence. Empty means "use the built-in default" — see
+    /// `http_client::effective_evm_rpc_endpoints`.
+    pub evm_rpc_endpoints: Vec<String>,
+    /// Bounded-retry behavior for `evm::wait_for_receipt_success`'s polling
+    /// loop. See `evm::ReceiptPollPolicy`.
+    pub receipt_poll_policy: crate::evm::ReceiptPollPolicy,
+    /// Principals allowed through `admin::require_controller`, the guard on
+    /// configuration and simulation endpoints. Seeded with the deploying
+    /// principal in `#[ic_cdk::init]`; grown or shrunk via
+    /// `admin::add_controller`/`admin::remove_controller`, which only an
+    /// existing controller may call.
+    pub controllers: BTreeSet<Principal>,
+}
+
+thread_local! {
+    pub static STATE: RefCell<State> = RefCell::new(State::default());
+}
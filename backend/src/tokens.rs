@@ -0,0 +1,210 @@
+use candid::Principal;
+
+use crate::errors::SwapError;
+use crate::state::STATE;
+
+pub fn is_token_paused(token: &str) -> bool {
+    STATE.with(|s| s.borrow().paused_tokens.get(token).copied().unwrap_or(false))
+}
+
+pub fn require_token_not_paused(token: &str) -> Result<(), SwapError> {
+    if is_token_paused(token) {
+        Err(SwapError::TokenPaused(token.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Admin-only: pause or resume a single token, isolating a buggy ledger
+/// without having to halt the whole canister.
+#[ic_cdk::update]
+pub fn set_token_paused(token: String, paused: bool) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| s.borrow_mut().paused_tokens.insert(token, paused));
+    Ok(())
+}
+
+/// Whether `token` (an ERC-20 contract address) reverts on `approve` from a
+/// non-zero allowance straight to another non-zero value, and so needs its
+/// allowance reset to zero first. See `evm::build_erc20_approve_calls`.
+pub fn requires_approval_reset(token: &str) -> bool {
+    STATE.with(|s| {
+        s.borrow()
+            .erc20_requires_approval_reset
+            .get(&crate::evm::normalize_evm_address(token))
+            .copied()
+            .unwrap_or(false)
+    })
+}
+
+/// Admin-only: flags (or unflags) an ERC-20 token as requiring a zero-reset
+/// approve before the real approve in the approve-fallback escrow path.
+#[ic_cdk::update]
+pub fn set_erc20_requires_approval_reset(token: String, requires_reset: bool) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| {
+        s.borrow_mut()
+            .erc20_requires_approval_reset
+            .insert(crate::evm::normalize_evm_address(&token), requires_reset);
+    });
+    Ok(())
+}
+
+/// Admin-only: sets (or clears, passing `None`) the dust floor for `token`.
+#[ic_cdk::update]
+pub fn set_token_min_amount(token: String, minimum: Option<u128>) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        match minimum {
+            Some(minimum) => {
+                state.min_amount_by_token.insert(token, minimum);
+            }
+            None => {
+                state.min_amount_by_token.remove(&token);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Admin-only: sets (or clears, passing `None`) the ceiling for `token`.
+#[ic_cdk::update]
+pub fn set_token_max_amount(token: String, maximum: Option<u128>) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        match maximum {
+            Some(maximum) => {
+                state.max_amount_by_token.insert(token, maximum);
+            }
+            None => {
+                state.max_amount_by_token.remove(&token);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Admin-only: sets (or clears, passing `None`) the ICRC-1 ledger canister
+/// backing `token`, so `icrc::get_icrc_allowance` knows where to look up an
+/// ICP-side maker's allowance for it.
+#[ic_cdk::update]
+pub fn set_icrc_ledger_canister(token: String, canister_id: Option<Principal>) -> Result<(), SwapError> {
+    crate::admin::require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        match canister_id {
+            Some(canister_id) => {
+                state.icrc_ledger_canisters.insert(token, canister_id);
+            }
+            None => {
+                state.icrc_ledger_canisters.remove(&token);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The ICRC-1 ledger canister configured for `token`, if any.
+pub fn icrc_ledger_canister_for(token: &str) -> Option<Principal> {
+    STATE.with(|s| s.borrow().icrc_ledger_canisters.get(token).copied())
+}
+
+/// Rejects `amount` if it falls outside `token`'s configured min/max, both
+/// of which default to unbounded (no entry) until an admin sets one.
+pub fn require_amount_within_bounds(token: &str, amount: u128) -> Result<(), SwapError> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        if let Some(&minimum) = state.min_amount_by_token.get(token) {
+            if amount < minimum {
+                return Err(SwapError::AmountBelowMinimum { token: token.to_string(), minimum, amount });
+            }
+        }
+        if let Some(&maximum) = state.max_amount_by_token.get(token) {
+            if amount > maximum {
+                return Err(SwapError::AmountAboveMaximum { token: token.to_string(), maximum, amount });
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reset_state() {
+        STATE.with(|s| *s.borrow_mut() = crate::state::State::default());
+    }
+
+    #[test]
+    fn unpaused_token_is_allowed() {
+        reset_state();
+        assert!(require_token_not_paused("ICP").is_ok());
+    }
+
+    #[test]
+    fn paused_token_is_rejected() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().paused_tokens.insert("ICP".into(), true));
+        assert_eq!(
+            require_token_not_paused("ICP"),
+            Err(SwapError::TokenPaused("ICP".into()))
+        );
+    }
+
+    #[test]
+    fn a_token_is_not_flagged_as_requiring_approval_reset_by_default() {
+        reset_state();
+        assert!(!requires_approval_reset("0xdAC17F958D2ee523a2206206994597C13D831ec"));
+    }
+
+    #[test]
+    fn an_amount_with_no_configured_bounds_is_always_within_bounds() {
+        reset_state();
+        assert!(require_amount_within_bounds("ICP", 1).is_ok());
+    }
+
+    #[test]
+    fn a_dust_sized_amount_is_rejected_below_the_configured_minimum() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().min_amount_by_token.insert("ICP".into(), 1_000));
+        assert_eq!(
+            require_amount_within_bounds("ICP", 10),
+            Err(SwapError::AmountBelowMinimum { token: "ICP".into(), minimum: 1_000, amount: 10 })
+        );
+    }
+
+    #[test]
+    fn an_in_range_amount_is_accepted() {
+        reset_state();
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            state.min_amount_by_token.insert("ICP".into(), 1_000);
+            state.max_amount_by_token.insert("ICP".into(), 1_000_000);
+        });
+        assert!(require_amount_within_bounds("ICP", 10_000).is_ok());
+    }
+
+    #[test]
+    fn an_amount_above_the_configured_maximum_is_rejected() {
+        reset_state();
+        STATE.with(|s| s.borrow_mut().max_amount_by_token.insert("ICP".into(), 1_000_000));
+        assert_eq!(
+            require_amount_within_bounds("ICP", 2_000_000),
+            Err(SwapError::AmountAboveMaximum { token: "ICP".into(), maximum: 1_000_000, amount: 2_000_000 })
+        );
+    }
+
+    #[test]
+    fn flagging_a_token_makes_it_require_approval_reset_regardless_of_address_case() {
+        reset_state();
+        STATE.with(|s| {
+            s.borrow_mut()
+                .erc20_requires_approval_reset
+                .insert("0xdac17f958d2ee523a2206206994597c13d831ec".into(), true);
+        });
+        assert!(requires_approval_reset("0xdAC17F958D2ee523a2206206994597C13D831ec"));
+    }
+}
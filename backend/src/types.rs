@@ -0,0 +1,211 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Chain {
+    ICP,
+    Ethereum,
+    Solana,
+    Base,
+    Arbitrum,
+}
+
+impl Chain {
+    /// Whether addresses on this chain are EVM-style `0x...` hex addresses.
+    pub fn is_evm(&self) -> bool {
+        matches!(self, Chain::Ethereum | Chain::Base | Chain::Arbitrum)
+    }
+}
+
+/// Key into `State::pairing_index`: an order's own `(src_chain, src_token,
+/// dst_chain, dst_token)`, i.e. the complementary leg a matching order must
+/// present. See `matching::pairing_index_key`.
+pub type PairingKey = (Chain, String, Chain, String);
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SwapOrderStatus {
+    Created,
+    Paired,
+    EscrowFunded,
+    /// Set right before the destination payout is released, and held for the
+    /// duration of that release, so a second near-simultaneous completion
+    /// call can't also pass the status check and double-release funds.
+    Completing,
+    Completed,
+    Cancelled,
+    Refunded,
+    /// A settlement leg (see `settlement::execute_atomic_swap`) failed and
+    /// hasn't been retried successfully yet. `SwapOrder::settlement_failure_reason`
+    /// carries why; `settlement::retry_settlement` resumes from the first leg
+    /// that didn't complete.
+    Failed,
+}
+
+/// 1inch-Fusion+-style tiered timelocks for a swap's two escrows, each
+/// offset in seconds from `SwapOrder::created_at`. The destination-side
+/// pair gates `htlc::complete_cross_chain_swap_public` (a taker may only
+/// reveal the secret and claim between `dst_withdrawal` and
+/// `dst_cancellation`); the source-side pair gates
+/// `orders::trigger_refund` (once `src_cancellation` elapses, anyone — not
+/// just the maker — may reclaim the source escrow).
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timelocks {
+    pub src_withdrawal: u64,
+    pub src_cancellation: u64,
+    pub dst_withdrawal: u64,
+    pub dst_cancellation: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SwapOrder {
+    pub id: String,
+    pub maker: Principal,
+    pub src_chain: Chain,
+    pub dst_chain: Chain,
+    pub src_token: String,
+    pub dst_token: String,
+    pub amount: u128,
+    pub destination_address: String,
+    pub safety_deposit: u128,
+    pub status: SwapOrderStatus,
+    pub created_at: u64,
+    pub completed_at: Option<u64>,
+    /// Opaque integrator-supplied reference for reconciliation. Never used in
+    /// security-sensitive logic (matching, claiming, refunding).
+    pub client_reference: Option<String>,
+    /// The amount actually observed to land in escrow, once deposited. Can be
+    /// below `amount` for fee-on-transfer (ERC-777 style) tokens that burn a
+    /// cut on transfer; all downstream accounting must use this, not `amount`.
+    pub actual_received_amount: Option<u128>,
+    /// When this order becomes eligible for refund if still unsettled.
+    pub expires_at: u64,
+    /// Deadline for the whole cross-chain coordination flow once paired,
+    /// distinct from `expires_at`'s HTLC-level timelock. Set when pairing
+    /// succeeds; if the swap hasn't reached a terminal state by then, the
+    /// heartbeat auto-refunds it rather than tying up escrow indefinitely.
+    pub coordination_deadline: Option<u64>,
+    /// How much of `amount` has already been matched against counter-orders
+    /// via partial fills. `amount - filled_amount` is what's still available
+    /// to match; see `matching::fill_order_internal` for how it's applied.
+    pub filled_amount: u128,
+    /// Irrecoverable cost the canister has already incurred setting up this
+    /// order's escrow (e.g. gas for creating its destination-chain HTLC).
+    /// Deducted from the refund on top of the chain's flat refund fee, since
+    /// that cost can't be clawed back once spent. Zero until
+    /// `create_htlc_escrow` records one.
+    pub sunk_setup_cost: u128,
+    /// Why the order's status last became `Failed`, if it ever did. Cleared
+    /// once a retried settlement completes successfully.
+    pub settlement_failure_reason: Option<String>,
+    /// The most recent settlement attempt's per-leg outcome, so a retry knows
+    /// which legs already succeeded and only needs to resume from the first
+    /// one that didn't.
+    pub last_settlement: Option<crate::settlement::AtomicSwapResult>,
+    /// Audit trail of real on-chain transfers backing this order's
+    /// settlement, appended to as each leg confirms. Unlike the ephemeral
+    /// event log, this is the durable, queryable record linking the order to
+    /// actual chain transactions. See `settlement::execute_atomic_swap`.
+    pub settlement: Vec<SettlementLeg>,
+    /// When set, the payout is split across these legs instead of going
+    /// entirely to `destination_address`. Legs sum to `amount` and are
+    /// validated against `dst_chain` at order creation; each is released
+    /// independently during completion, so a retried completion only
+    /// re-attempts legs that haven't released yet. See
+    /// `htlc::release_split_destinations`.
+    pub destinations: Option<Vec<PayoutDestination>>,
+    /// When set, an unpaired (`Created`) order is auto-cancelled and
+    /// refunded once `now` reaches this deadline, instead of waiting for the
+    /// much longer `expires_at` timelock. `None` means the maker is willing
+    /// to wait indefinitely for a match, up to the hard timelock. See
+    /// `orders::sweep_auto_refund_orders`.
+    pub auto_refund_after: Option<u64>,
+    /// Withdrawal/cancellation windows for this order's two escrows. See
+    /// `Timelocks`.
+    pub timelocks: Timelocks,
+}
+
+/// A caller-supplied split-payout leg: pay `amount` of the destination
+/// token to `address` instead of sending the whole order to a single
+/// `destination_address`. The full set must sum to the order's `amount`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PayoutDestinationRequest {
+    pub address: String,
+    pub amount: u128,
+}
+
+/// One leg of a split payout as tracked on the order itself, so completion
+/// is resumable: `released` flips to `true` only once that leg's transfer
+/// actually confirms.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PayoutDestination {
+    pub address: String,
+    pub amount: u128,
+    pub released: bool,
+}
+
+/// Whether a confirmed `SettlementLeg` funded an HTLC or claimed one.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SettlementDirection {
+    Fund,
+    Claim,
+}
+
+/// A single confirmed on-chain transfer belonging to a swap's settlement.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SettlementLeg {
+    pub chain: Chain,
+    pub direction: SettlementDirection,
+    pub tx_hash: String,
+    pub amount: u128,
+    pub token: String,
+}
+
+/// Parameters for `create_cross_chain_swap_order`, grouped into a request
+/// struct since the field count keeps growing with new order options.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreateOrderRequest {
+    pub src_chain: Chain,
+    pub dst_chain: Chain,
+    pub src_token: String,
+    pub dst_token: String,
+    pub amount: u128,
+    pub destination_address: String,
+    pub escrowed_safety_deposit: u128,
+    pub client_reference: Option<String>,
+    /// Optional split payout: if set, legs must sum to `amount` and each
+    /// address must be valid for `dst_chain`. `None` means the whole payout
+    /// goes to `destination_address`, as before.
+    pub destinations: Option<Vec<PayoutDestinationRequest>>,
+    /// Optional auto-refund window for this order while it sits unpaired.
+    /// See `SwapOrder::auto_refund_after`.
+    pub auto_refund_after: Option<u64>,
+    /// Optional override of the default `Timelocks` windows (see
+    /// `CanisterConfig::default_timelocks`). `None` uses the canister-wide
+    /// default.
+    pub timelocks: Option<Timelocks>,
+}
+
+/// A privacy-preserving view of a completed swap: no addresses, principals, or secrets.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SwapSummary {
+    pub src_chain: Chain,
+    pub dst_chain: Chain,
+    pub src_token: String,
+    pub dst_token: String,
+    pub amount: u128,
+    pub completed_at: u64,
+}
+
+impl From<&SwapOrder> for SwapSummary {
+    fn from(order: &SwapOrder) -> Self {
+        SwapSummary {
+            src_chain: order.src_chain,
+            dst_chain: order.dst_chain,
+            src_token: order.src_token.clone(),
+            dst_token: order.dst_token.clone(),
+            amount: order.amount,
+            completed_at: order.completed_at.unwrap_or(order.created_at),
+        }
+    }
+}